@@ -0,0 +1,436 @@
+//! Replays a recorded heartbeat arrival log through the failure detector.
+//!
+//! - `replay` reports suspicions, false positives and detection times for the
+//!   given configuration.
+//! - `sweep` evaluates a grid of configurations with crashes injected into the
+//!   log, prints the Pareto frontier of detection time and mistake rate, and
+//!   recommends a configuration meeting the target mistake rate.
+//!
+//! The log contains the arrival times of heartbeats, in milliseconds, per peer,
+//! either as CSV lines of `peer,timestamp_ms` (with an optional header), or as
+//! JSON lines of `{"peer": "a", "timestamp_ms": 1000}`.
+
+use {
+    clap::{Parser, Subcommand, ValueEnum},
+    phi_accrual_failure_detector::{
+        replay::{Arrival, PeerReport, Replay},
+        sweep::{self, Grid, Sweep, SweepPoint},
+        Config,
+    },
+    serde::Deserialize,
+    std::{
+        error::Error,
+        fs,
+        io::{self, Write},
+        path::{Path, PathBuf},
+        process::ExitCode,
+        time::Duration,
+    },
+};
+
+#[derive(Parser)]
+#[command(
+    version,
+    about = "Replay a heartbeat arrival log through the phi accrual failure detector"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Replay the log with a single configuration.
+    Replay(ReplayArgs),
+
+    /// Sweep a grid of configurations over the log.
+    Sweep(SweepArgs),
+}
+
+#[derive(clap::Args)]
+struct InputArgs {
+    /// Heartbeat arrival log.
+    file: PathBuf,
+
+    /// Format of the log. Inferred from the file extension by default.
+    #[arg(long, value_enum)]
+    format: Option<Format>,
+}
+
+#[derive(clap::Args)]
+struct ReplayArgs {
+    #[command(flatten)]
+    input: InputArgs,
+
+    #[command(flatten)]
+    config: ConfigArgs,
+
+    /// End of the trace in milliseconds. Defaults to the last arrival.
+    #[arg(long, value_name = "MS")]
+    end_ms: Option<u64>,
+
+    /// Print phi of every peer sampled at this interval, as CSV.
+    #[arg(long, value_name = "MS")]
+    phi_interval_ms: Option<u64>,
+}
+
+#[derive(clap::Args)]
+struct SweepArgs {
+    #[command(flatten)]
+    input: InputArgs,
+
+    /// Thresholds to sweep. Defaults to 1, 2, 3, 5, 8, 10, 12 and 16.
+    #[arg(long, value_delimiter = ',')]
+    thresholds: Vec<f64>,
+
+    /// Acceptable heartbeat pauses to sweep, in milliseconds. Defaults to 0,
+    /// 500, 1000, 2000, 3000 and 5000.
+    #[arg(long, value_name = "MS", value_delimiter = ',')]
+    pauses_ms: Vec<u64>,
+
+    /// Maximum sample sizes to sweep. Defaults to 50, 100, 200 and 1000.
+    #[arg(long, value_delimiter = ',')]
+    sample_sizes: Vec<usize>,
+
+    /// Minimum standard deviation of heartbeat intervals, in milliseconds.
+    #[arg(long, value_name = "MS", default_value_t = ms(Config::default().min_std_deviation))]
+    min_std_deviation_ms: u64,
+
+    /// First heartbeat interval estimate, in milliseconds.
+    #[arg(long, value_name = "MS", default_value_t = ms(Config::default().first_heartbeat_estimate))]
+    first_heartbeat_estimate_ms: u64,
+
+    /// Times to inject crashes at, in milliseconds. Defaults to a quarter, half
+    /// and three quarters of the log.
+    #[arg(long, value_name = "MS", value_delimiter = ',')]
+    crash_at_ms: Vec<u64>,
+
+    /// Maximum acceptable false positive suspicions per hour of monitoring of
+    /// all peers.
+    #[arg(long, default_value_t = 1.)]
+    target_mistake_rate: f64,
+}
+
+#[derive(clap::Args)]
+struct ConfigArgs {
+    /// Suspicion threshold.
+    #[arg(long, default_value_t = Config::default().threshold)]
+    threshold: f64,
+
+    /// Maximum number of heartbeat intervals retained in the history.
+    #[arg(long, default_value_t = Config::default().max_sample_size)]
+    max_sample_size: usize,
+
+    /// Minimum standard deviation of heartbeat intervals, in milliseconds.
+    #[arg(long, value_name = "MS", default_value_t = ms(Config::default().min_std_deviation))]
+    min_std_deviation_ms: u64,
+
+    /// Acceptable heartbeat pause, in milliseconds.
+    #[arg(long, value_name = "MS", default_value_t = ms(Config::default().acceptable_heartbeat_pause))]
+    acceptable_heartbeat_pause_ms: u64,
+
+    /// First heartbeat interval estimate, in milliseconds.
+    #[arg(long, value_name = "MS", default_value_t = ms(Config::default().first_heartbeat_estimate))]
+    first_heartbeat_estimate_ms: u64,
+}
+
+impl From<ConfigArgs> for Config {
+    fn from(args: ConfigArgs) -> Self {
+        Self {
+            threshold: args.threshold,
+            max_sample_size: args.max_sample_size,
+            min_std_deviation: Duration::from_millis(args.min_std_deviation_ms),
+            acceptable_heartbeat_pause: Duration::from_millis(args.acceptable_heartbeat_pause_ms),
+            first_heartbeat_estimate: Duration::from_millis(args.first_heartbeat_estimate_ms),
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Csv,
+    Jsonl,
+}
+
+#[derive(Deserialize)]
+struct Record {
+    peer: String,
+    timestamp_ms: f64,
+}
+
+fn main() -> ExitCode {
+    let result = match Cli::parse().command {
+        Command::Replay(args) => replay(args),
+        Command::Sweep(args) => sweep(args),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn replay(args: ReplayArgs) -> Result<(), Box<dyn Error>> {
+    let arrivals = read_arrivals(&args.input)?;
+    let mut replay = Replay::new(args.config.into());
+
+    if let Some(end) = args.end_ms {
+        replay = replay.end(Duration::from_millis(end));
+    }
+
+    if let Some(interval) = args.phi_interval_ms {
+        replay = replay.sample_interval(Duration::from_millis(interval));
+    }
+
+    let reports = replay.run(arrivals)?;
+    let mut out = io::stdout().lock();
+
+    if args.phi_interval_ms.is_some() {
+        print_phi(&mut out, &reports)?;
+    } else {
+        print_summary(&mut out, &reports)?;
+    }
+
+    Ok(())
+}
+
+fn sweep(args: SweepArgs) -> Result<(), Box<dyn Error>> {
+    let arrivals = read_arrivals(&args.input)?;
+    let default = Grid::default();
+
+    let grid = Grid {
+        thresholds: or_default(args.thresholds, default.thresholds),
+        acceptable_heartbeat_pauses: or_default(
+            args.pauses_ms
+                .into_iter()
+                .map(Duration::from_millis)
+                .collect(),
+            default.acceptable_heartbeat_pauses,
+        ),
+        max_sample_sizes: or_default(args.sample_sizes, default.max_sample_sizes),
+    };
+
+    let base = Config {
+        min_std_deviation: Duration::from_millis(args.min_std_deviation_ms),
+        first_heartbeat_estimate: Duration::from_millis(args.first_heartbeat_estimate_ms),
+        ..Default::default()
+    };
+
+    let points = Sweep::new(grid)
+        .base(base)
+        .crash_points(
+            args.crash_at_ms
+                .into_iter()
+                .map(Duration::from_millis)
+                .collect(),
+        )
+        .run(&arrivals)?;
+
+    let mut out = io::stdout().lock();
+
+    writeln!(
+        out,
+        "threshold,acceptable_heartbeat_pause_ms,max_sample_size,mean_detection_ms,\
+         max_detection_ms,undetected,false_positives,mistakes_per_hour"
+    )?;
+
+    for point in sweep::pareto_frontier(&points) {
+        print_point(&mut out, &point)?;
+    }
+
+    writeln!(out)?;
+
+    match sweep::recommend(&points, args.target_mistake_rate) {
+        Some(point) => {
+            writeln!(out, "recommended:")?;
+            print_point(&mut out, point)?;
+        }
+
+        None => writeln!(
+            out,
+            "no configuration detects all crashes within the target mistake rate"
+        )?,
+    }
+
+    Ok(())
+}
+
+fn or_default<T>(values: Vec<T>, default: Vec<T>) -> Vec<T> {
+    if values.is_empty() {
+        default
+    } else {
+        values
+    }
+}
+
+fn read_arrivals(args: &InputArgs) -> Result<Vec<Arrival>, Box<dyn Error>> {
+    let input = fs::read_to_string(&args.file)
+        .map_err(|err| format!("failed to read {}: {err}", args.file.display()))?;
+
+    let arrivals = match args.format.unwrap_or_else(|| infer_format(&args.file)) {
+        Format::Csv => parse_csv(&input)?,
+        Format::Jsonl => parse_jsonl(&input)?,
+    };
+
+    if arrivals.is_empty() {
+        return Err("no heartbeat arrivals in the log".into());
+    }
+
+    Ok(arrivals)
+}
+
+fn infer_format(path: &Path) -> Format {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("jsonl" | "ndjson" | "json") => Format::Jsonl,
+        _ => Format::Csv,
+    }
+}
+
+fn parse_csv(input: &str) -> Result<Vec<Arrival>, Box<dyn Error>> {
+    let mut arrivals = Vec::new();
+
+    for (i, line) in input.lines().enumerate() {
+        let line = line.trim();
+
+        if line.is_empty() {
+            continue;
+        }
+
+        let Some((peer, timestamp)) = line.rsplit_once(',') else {
+            return Err(format!("line {}: expected `peer,timestamp_ms`", i + 1).into());
+        };
+
+        let Ok(timestamp) = timestamp.trim().parse() else {
+            // Skip the header.
+            if i == 0 {
+                continue;
+            }
+
+            return Err(format!("line {}: invalid timestamp `{timestamp}`", i + 1).into());
+        };
+
+        arrivals.push(arrival(peer.trim().to_owned(), timestamp, i)?);
+    }
+
+    Ok(arrivals)
+}
+
+fn parse_jsonl(input: &str) -> Result<Vec<Arrival>, Box<dyn Error>> {
+    let mut arrivals = Vec::new();
+
+    for (i, line) in input.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let record: Record =
+            serde_json::from_str(line).map_err(|err| format!("line {}: {err}", i + 1))?;
+
+        arrivals.push(arrival(record.peer, record.timestamp_ms, i)?);
+    }
+
+    Ok(arrivals)
+}
+
+fn arrival(peer: String, timestamp_ms: f64, line: usize) -> Result<Arrival, Box<dyn Error>> {
+    let time = Duration::try_from_secs_f64(timestamp_ms / 1000.)
+        .map_err(|_| format!("line {}: invalid timestamp `{timestamp_ms}`", line + 1))?;
+
+    Ok(Arrival { peer, time })
+}
+
+fn print_summary(out: &mut impl Write, reports: &[PeerReport]) -> io::Result<()> {
+    let mut false_positives = 0;
+    let mut detection_times = Vec::new();
+
+    for report in reports {
+        writeln!(
+            out,
+            "{}: {} heartbeats, {} suspicions, {} false positives ({} mistake duration)",
+            report.peer,
+            report.heartbeats,
+            report.suspicions.len(),
+            report.false_positives(),
+            seconds(report.mistake_duration()),
+        )?;
+
+        for suspicion in &report.suspicions {
+            match suspicion.end {
+                Some(end) => writeln!(
+                    out,
+                    "  suspected at {}, recovered at {} (false positive)",
+                    seconds(suspicion.start),
+                    seconds(end)
+                )?,
+
+                None => writeln!(
+                    out,
+                    "  suspected at {}, {} after last heartbeat (detection)",
+                    seconds(suspicion.start),
+                    seconds(suspicion.start - report.last_heartbeat)
+                )?,
+            }
+        }
+
+        false_positives += report.false_positives();
+        detection_times.extend(report.detection_time());
+    }
+
+    writeln!(out)?;
+    writeln!(out, "peers: {}", reports.len())?;
+    writeln!(out, "false positives: {false_positives}")?;
+    writeln!(out, "detections: {}", detection_times.len())?;
+
+    if !detection_times.is_empty() {
+        let mean = detection_times.iter().sum::<Duration>() / detection_times.len() as u32;
+        // Safe unwrap, the list is not empty.
+        let max = detection_times.iter().max().unwrap();
+
+        writeln!(out, "mean detection time: {}", seconds(mean))?;
+        writeln!(out, "max detection time: {}", seconds(*max))?;
+    }
+
+    Ok(())
+}
+
+fn print_phi(out: &mut impl Write, reports: &[PeerReport]) -> io::Result<()> {
+    writeln!(out, "peer,timestamp_ms,phi")?;
+
+    for report in reports {
+        for (time, phi) in &report.phi {
+            // Avoid printing rounding errors as `-0.000`.
+            let phi = if *phi > 0. { *phi } else { 0. };
+            writeln!(out, "{},{},{phi:.3}", report.peer, time.as_millis())?;
+        }
+    }
+
+    Ok(())
+}
+
+fn print_point(out: &mut impl Write, point: &SweepPoint) -> io::Result<()> {
+    let config = &point.config;
+
+    writeln!(
+        out,
+        "{},{},{},{},{},{},{},{:.3}",
+        config.threshold,
+        config.acceptable_heartbeat_pause.as_millis(),
+        config.max_sample_size,
+        point.mean_detection_time.as_millis(),
+        point.max_detection_time.as_millis(),
+        point.undetected,
+        point.false_positives,
+        point.mistake_rate,
+    )
+}
+
+fn seconds(duration: Duration) -> String {
+    format!("{:.3}s", duration.as_secs_f64())
+}
+
+fn ms(duration: Duration) -> u64 {
+    duration.as_millis() as u64
+}
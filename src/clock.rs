@@ -0,0 +1,394 @@
+use {
+    crate::Clock,
+    std::{
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Arc,
+            Mutex,
+        },
+        thread,
+        time::{Duration, Instant, SystemTime},
+    },
+};
+
+/// Clock that only moves when told to, e.g. to drive detectors in tests and
+/// simulations.
+///
+/// Timestamps are the time since an arbitrary epoch, initially zero, with
+/// nanosecond resolution. Clones share the same time, so a single handle can
+/// drive many detectors in lockstep, also across threads.
+///
+/// ```
+/// use {
+///     phi_accrual_failure_detector::{Detector, ManualClock, SyncDetector},
+///     std::time::Duration,
+/// };
+///
+/// let clock = ManualClock::new();
+/// let detectors: Vec<_> = (0..3)
+///     .map(|_| {
+///         SyncDetector::builder()
+///             .clock(clock.clone())
+///             .build()
+///             .unwrap()
+///     })
+///     .collect();
+///
+/// for _ in 0..10 {
+///     detectors.iter().for_each(|detector| detector.heartbeat());
+///     clock.advance(Duration::from_secs(1));
+/// }
+///
+/// clock.advance(Duration::from_secs(10));
+/// assert!(detectors.iter().all(|detector| !detector.is_available()));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ManualClock(Arc<AtomicU64>);
+
+impl ManualClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Moves the time forward by `duration`.
+    pub fn advance(&self, duration: Duration) {
+        self.0
+            .fetch_add(duration.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    /// Sets the time to `timestamp`, which may also move it backward.
+    pub fn set(&self, timestamp: Duration) {
+        self.0.store(timestamp.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    /// Returns the current time.
+    pub fn now(&self) -> Duration {
+        Duration::from_nanos(self.0.load(Ordering::Relaxed))
+    }
+}
+
+impl Clock for ManualClock {
+    type Timestamp = Duration;
+
+    fn timestamp(&self) -> Self::Timestamp {
+        self.now()
+    }
+
+    fn elapsed(before: &Self::Timestamp, after: &Self::Timestamp) -> Duration {
+        after.saturating_sub(*before)
+    }
+}
+
+/// Clock based on [`tokio::time::Instant`], which follows tokio's virtual time
+/// when it's paused, e.g. with `tokio::time::pause` or
+/// `#[tokio::test(start_paused = true)]`, so that detectors can be tested
+/// deterministically along with the tokio code driving them.
+///
+/// Outside of a runtime with paused time it behaves like
+/// [`DefaultClock`](crate::DefaultClock).
+#[cfg(feature = "tokio")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokioClock;
+
+#[cfg(feature = "tokio")]
+impl Clock for TokioClock {
+    type Timestamp = tokio::time::Instant;
+
+    fn timestamp(&self) -> Self::Timestamp {
+        tokio::time::Instant::now()
+    }
+
+    fn elapsed(before: &Self::Timestamp, after: &Self::Timestamp) -> Duration {
+        after.saturating_duration_since(*before)
+    }
+}
+
+/// Clock reading a timestamp cached by a background ticker thread, for hot
+/// paths where calling [`Instant::now`] on every heartbeat and phi query is
+/// too expensive.
+///
+/// The ticker thread refreshes the timestamp every `resolution`, and exits
+/// once all clones of the clock have been dropped. Reading the clock is a
+/// single atomic load, at the cost of precision:
+///
+/// - Timestamps lag behind real time by up to the resolution, so heartbeat
+///   intervals and the time since the last heartbeat are measured with an error
+///   of up to the resolution in either direction. Suspicions may therefore be
+///   raised up to the resolution earlier or later than with
+///   [`DefaultClock`](crate::DefaultClock).
+/// - If the ticker thread isn't scheduled in time, e.g. when the process is
+///   starved of CPU, the clock stalls, which delays suspicions rather than
+///   causing them.
+///
+/// The resolution should therefore be well below both the heartbeat interval
+/// and the configured minimum standard deviation, e.g. a few milliseconds with
+/// the defaults.
+///
+/// To observe the effect of the resolution without a ticker thread, the clock
+/// can also be created with [`CoarseClock::manual`], reading the time of a
+/// [`ManualClock`] on every [`CoarseClock::tick`].
+#[derive(Debug, Clone)]
+pub struct CoarseClock(Arc<CoarseTime>);
+
+#[derive(Debug)]
+struct CoarseTime {
+    start: Instant,
+    source: Option<ManualClock>,
+    elapsed: AtomicU64,
+    resolution: Duration,
+}
+
+impl CoarseClock {
+    /// Creates the clock and spawns its ticker thread.
+    ///
+    /// Panics if `resolution` is zero.
+    pub fn new(resolution: Duration) -> Self {
+        assert!(!resolution.is_zero(), "resolution must be > 0");
+
+        let clock = Self::with_source(resolution, None);
+        let weak = Arc::downgrade(&clock.0);
+
+        thread::spawn(move || {
+            while let Some(time) = weak.upgrade() {
+                CoarseClock(time).tick();
+                thread::sleep(resolution);
+            }
+        });
+
+        clock
+    }
+
+    /// Creates the clock without a ticker thread. The cached timestamp is
+    /// refreshed from the time of `source` only when [`CoarseClock::tick`] is
+    /// called, e.g. every `resolution` of manual time in tests.
+    ///
+    /// Panics if `resolution` is zero.
+    pub fn manual(resolution: Duration, source: ManualClock) -> Self {
+        assert!(!resolution.is_zero(), "resolution must be > 0");
+
+        Self::with_source(resolution, Some(source))
+    }
+
+    fn with_source(resolution: Duration, source: Option<ManualClock>) -> Self {
+        let clock = Self(Arc::new(CoarseTime {
+            start: Instant::now(),
+            source,
+            elapsed: AtomicU64::new(0),
+            resolution,
+        }));
+
+        clock.tick();
+        clock
+    }
+
+    /// Refreshes the cached timestamp. Called by the ticker thread every
+    /// `resolution`.
+    pub fn tick(&self) {
+        let elapsed = match &self.0.source {
+            Some(source) => source.now(),
+            None => self.0.start.elapsed(),
+        };
+
+        self.0
+            .elapsed
+            .store(elapsed.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    /// Interval the cached timestamp is refreshed at.
+    pub fn resolution(&self) -> Duration {
+        self.0.resolution
+    }
+}
+
+impl Clock for CoarseClock {
+    type Timestamp = Instant;
+
+    fn timestamp(&self) -> Self::Timestamp {
+        self.0.start + Duration::from_nanos(self.0.elapsed.load(Ordering::Relaxed))
+    }
+
+    fn elapsed(before: &Self::Timestamp, after: &Self::Timestamp) -> Duration {
+        after.saturating_duration_since(*before)
+    }
+}
+
+/// Clock based on [`SystemTime`], i.e. the wall clock, guarding the detector
+/// against the wall clock being adjusted.
+///
+/// Every reading is compared with the monotonic [`Instant`] elapsed since the
+/// previous one:
+///
+/// - Backward jumps are excluded from the timestamps, which advance by the
+///   monotonic time elapsed instead, so that they neither go back nor stand
+///   still until the wall clock catches up.
+/// - Forward jumps beyond [`SystemClock::max_forward_jump`] are excluded from
+///   the timestamps, so that they don't count towards the time since the last
+///   heartbeat nor the heartbeat intervals, rather than spiking phi.
+///
+/// Excluded jumps are reported by [`SystemClock::jumps`] and
+/// [`SystemClock::excluded`]. Clones share the jump tracking.
+#[derive(Debug, Clone, Default)]
+pub struct SystemClock(Arc<Mutex<SystemTimeState>>);
+
+#[derive(Debug)]
+struct SystemTimeState {
+    max_forward_jump: Duration,
+    last: Option<Reading>,
+    jumps: u64,
+    excluded: Duration,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Reading {
+    wall: SystemTime,
+    monotonic: Instant,
+    timestamp: SystemTime,
+}
+
+impl Default for SystemTimeState {
+    fn default() -> Self {
+        Self {
+            max_forward_jump: Duration::from_secs(1),
+            last: None,
+            jumps: 0,
+            excluded: Duration::ZERO,
+        }
+    }
+}
+
+impl SystemClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Maximum difference between the wall clock and the monotonic time
+    /// elapsed between two readings, beyond which the wall clock is considered
+    /// to have jumped forward.
+    ///
+    /// Default: 1s
+    pub fn max_forward_jump(self, max_forward_jump: Duration) -> Self {
+        self.0.lock().unwrap().max_forward_jump = max_forward_jump;
+        self
+    }
+
+    /// Number of jumps, backward or forward, excluded so far.
+    pub fn jumps(&self) -> u64 {
+        self.0.lock().unwrap().jumps
+    }
+
+    /// Total duration of the jumps, backward or forward, excluded so far.
+    pub fn excluded(&self) -> Duration {
+        self.0.lock().unwrap().excluded
+    }
+}
+
+impl SystemTimeState {
+    fn read(&mut self, wall: SystemTime, monotonic: Instant) -> SystemTime {
+        let Some(last) = self.last else {
+            self.last = Some(Reading {
+                wall,
+                monotonic,
+                timestamp: wall,
+            });
+
+            return wall;
+        };
+
+        let monotonic_elapsed = monotonic.saturating_duration_since(last.monotonic);
+
+        // Timestamps follow the wall clock with the jumps excluded so far, and
+        // follow the monotonic time across a new jump.
+        let timestamp = match wall.duration_since(last.wall) {
+            Ok(wall_elapsed) if wall_elapsed <= monotonic_elapsed + self.max_forward_jump => {
+                last.timestamp + wall_elapsed
+            }
+
+            Ok(wall_elapsed) => {
+                let jump = wall_elapsed - monotonic_elapsed;
+
+                #[cfg(feature = "tracing")]
+                tracing::warn!(
+                    jump_ms = jump.as_millis() as u64,
+                    "system clock jumped forward"
+                );
+
+                self.jumps += 1;
+                self.excluded += jump;
+                last.timestamp + monotonic_elapsed
+            }
+
+            Err(err) => {
+                let jump = err.duration() + monotonic_elapsed;
+
+                #[cfg(feature = "tracing")]
+                tracing::warn!(
+                    jump_ms = jump.as_millis() as u64,
+                    "system clock jumped backward"
+                );
+
+                self.jumps += 1;
+                self.excluded += jump;
+                last.timestamp + monotonic_elapsed
+            }
+        };
+
+        self.last = Some(Reading {
+            wall,
+            monotonic,
+            timestamp,
+        });
+
+        timestamp
+    }
+}
+
+impl Clock for SystemClock {
+    type Timestamp = SystemTime;
+
+    fn timestamp(&self) -> Self::Timestamp {
+        let (wall, monotonic) = (SystemTime::now(), Instant::now());
+        self.0.lock().unwrap().read(wall, monotonic)
+    }
+
+    fn elapsed(before: &Self::Timestamp, after: &Self::Timestamp) -> Duration {
+        after.duration_since(*before).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, std::time::UNIX_EPOCH};
+
+    #[test]
+    fn system_clock_jumps() {
+        let mut state = SystemTimeState::default();
+        let (wall, monotonic) = (UNIX_EPOCH + Duration::from_secs(1000), Instant::now());
+        let at = |secs: u64| Duration::from_secs(secs);
+
+        assert_eq!(state.read(wall, monotonic), wall);
+        assert_eq!(state.read(wall + at(1), monotonic + at(1)), wall + at(1));
+
+        // Backward jump of 5s is excluded, the clock keeps moving with the
+        // monotonic time and then follows the wall clock again.
+        assert_eq!(state.read(wall - at(3), monotonic + at(2)), wall + at(2));
+        assert_eq!(state.read(wall - at(2), monotonic + at(3)), wall + at(3));
+        assert_eq!(state.read(wall, monotonic + at(5)), wall + at(5));
+        assert_eq!(state.jumps, 1);
+        assert_eq!(state.excluded, at(5));
+
+        // Forward jump of 1h within a 1s reading interval is excluded.
+        let jumped = wall + at(3601);
+        assert_eq!(state.read(jumped, monotonic + at(6)), wall + at(6));
+        assert_eq!(state.read(jumped + at(1), monotonic + at(7)), wall + at(7));
+        assert_eq!(state.jumps, 2);
+        assert_eq!(state.excluded, at(3605));
+
+        // Drift within the bound is kept.
+        let drifted = jumped + at(2) + Duration::from_millis(500);
+        assert_eq!(
+            state.read(drifted, monotonic + at(8)),
+            wall + at(8) + Duration::from_millis(500)
+        );
+        assert_eq!(state.jumps, 2);
+    }
+}
@@ -0,0 +1,265 @@
+//! Split brain resolver deciding which members to down once detectors mark
+//! them unreachable.
+//!
+//! Implements the strategies of the Pekko split brain resolver. Each node
+//! decides independently, based on its own view of which members are
+//! unreachable, e.g. derived from its [`Registry`](crate::Registry) or
+//! [`Reachability`](crate::Reachability) table. The strategies are designed so
+//! that, when the cluster is split into partitions that can't reach each other,
+//! at most one partition keeps running.
+//!
+//! Decisions are only made once the set of unreachable members has been stable
+//! for the `stable_after` period, so that transient unreachability doesn't
+//! down anyone.
+
+use {
+    crate::{Clock, DefaultClock},
+    std::{collections::BTreeSet, time::Duration},
+};
+
+/// Member of the cluster.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Member<I> {
+    pub id: I,
+
+    /// Order in which the member has joined the cluster. The member with the
+    /// lowest number is the oldest one.
+    pub up_number: u64,
+}
+
+/// Strategy deciding which side of a partition is downed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Strategy<I> {
+    /// Keep the side with the majority of members. If both sides have the same
+    /// size, keep the side with the lowest member identifier.
+    KeepMajority,
+
+    /// Keep the side with the oldest member. With `down_if_alone`, the oldest
+    /// member is downed instead if it's alone on its side.
+    KeepOldest { down_if_alone: bool },
+
+    /// Keep the side with at least `quorum_size` members. The cluster must not
+    /// grow beyond `quorum_size * 2 - 1` members, otherwise all members are
+    /// downed.
+    StaticQuorum { quorum_size: usize },
+
+    /// Keep the side with the `referee` member, unless it has fewer than
+    /// `down_all_if_less_than` members, in which case all members are downed.
+    KeepReferee {
+        referee: I,
+        down_all_if_less_than: usize,
+    },
+
+    /// Down all members.
+    DownAll,
+}
+
+/// Decision made from the point of view of a particular node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    /// Down the unreachable members, keeping the local side.
+    DownUnreachable,
+
+    /// Down the reachable members, including the local node.
+    DownReachable,
+
+    /// Down all members.
+    DownAll,
+}
+
+impl Decision {
+    /// Returns `true` if the local side is downed.
+    pub fn downs_self(self) -> bool {
+        self != Self::DownUnreachable
+    }
+}
+
+/// Decision along with the members to down, sorted by identifier.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Downing<I> {
+    pub decision: Decision,
+    pub down: Vec<I>,
+}
+
+impl<I: Ord + Clone> Strategy<I> {
+    /// Decides which side to down. Unreachable members that aren't in
+    /// `members` are ignored.
+    pub fn decide(&self, members: &[Member<I>], unreachable: &BTreeSet<I>) -> Decision {
+        let (unreachable, reachable): (Vec<_>, Vec<_>) = members
+            .iter()
+            .partition(|member| unreachable.contains(&member.id));
+
+        match self {
+            Self::KeepMajority => {
+                if reachable.len() != unreachable.len() {
+                    return if reachable.len() > unreachable.len() {
+                        Decision::DownUnreachable
+                    } else {
+                        Decision::DownReachable
+                    };
+                }
+
+                let lowest_reachable = reachable.iter().map(|member| &member.id).min();
+                let lowest_unreachable = unreachable.iter().map(|member| &member.id).min();
+
+                if lowest_reachable < lowest_unreachable {
+                    Decision::DownUnreachable
+                } else {
+                    Decision::DownReachable
+                }
+            }
+
+            Self::KeepOldest { down_if_alone } => {
+                let Some(oldest) = members
+                    .iter()
+                    .min_by(|a, b| (a.up_number, &a.id).cmp(&(b.up_number, &b.id)))
+                else {
+                    return Decision::DownUnreachable;
+                };
+
+                let oldest_reachable = !unreachable.contains(&oldest);
+                let (oldest_side, other_side) = if oldest_reachable {
+                    (reachable.len(), unreachable.len())
+                } else {
+                    (unreachable.len(), reachable.len())
+                };
+
+                let oldest_downed = *down_if_alone && oldest_side == 1 && other_side > 0;
+
+                if oldest_reachable != oldest_downed {
+                    Decision::DownUnreachable
+                } else {
+                    Decision::DownReachable
+                }
+            }
+
+            Self::StaticQuorum { quorum_size } => {
+                if members.len() >= quorum_size * 2 {
+                    Decision::DownAll
+                } else if reachable.len() < *quorum_size {
+                    Decision::DownReachable
+                } else {
+                    Decision::DownUnreachable
+                }
+            }
+
+            Self::KeepReferee {
+                referee,
+                down_all_if_less_than,
+            } => {
+                if !reachable.iter().any(|member| member.id == *referee) {
+                    Decision::DownReachable
+                } else if reachable.len() < *down_all_if_less_than {
+                    Decision::DownAll
+                } else {
+                    Decision::DownUnreachable
+                }
+            }
+
+            Self::DownAll => Decision::DownAll,
+        }
+    }
+
+    /// Decides which side to down, returning the members to down.
+    pub fn resolve(&self, members: &[Member<I>], unreachable: &BTreeSet<I>) -> Downing<I> {
+        let decision = self.decide(members, unreachable);
+
+        let mut down: Vec<_> = members
+            .iter()
+            .filter(|member| match decision {
+                Decision::DownUnreachable => unreachable.contains(&member.id),
+                Decision::DownReachable => !unreachable.contains(&member.id),
+                Decision::DownAll => true,
+            })
+            .map(|member| member.id.clone())
+            .collect();
+
+        down.sort();
+
+        Downing { decision, down }
+    }
+}
+
+/// Split brain resolver applying a [`Strategy`] once the unreachable members
+/// have been stable for a period of time.
+pub struct SplitBrainResolver<I, C: Clock = DefaultClock> {
+    strategy: Strategy<I>,
+    stable_after: Duration,
+    clock: C,
+    members: Vec<Member<I>>,
+    unreachable: BTreeSet<I>,
+    stable_since: Option<C::Timestamp>,
+    decided: bool,
+}
+
+impl<I: Ord + Clone> SplitBrainResolver<I> {
+    pub fn new(strategy: Strategy<I>, stable_after: Duration) -> Self {
+        Self {
+            strategy,
+            stable_after,
+            clock: DefaultClock,
+            members: Vec::new(),
+            unreachable: BTreeSet::new(),
+            stable_since: None,
+            decided: false,
+        }
+    }
+}
+
+impl<I: Ord + Clone, C: Clock> SplitBrainResolver<I, C> {
+    /// Provide an alternative implementation of [`Clock`].
+    ///
+    /// Default: [`DefaultClock`]
+    pub fn clock<T: Clock>(self, clock: T) -> SplitBrainResolver<I, T> {
+        SplitBrainResolver {
+            strategy: self.strategy,
+            stable_after: self.stable_after,
+            clock,
+            members: self.members,
+            unreachable: self.unreachable,
+            stable_since: None,
+            decided: false,
+        }
+    }
+
+    /// Updates the view of the cluster, returning the decision once the view
+    /// has been stable with some members unreachable for the `stable_after`
+    /// period. The decision is returned once per stable view.
+    pub fn update(
+        &mut self,
+        members: &[Member<I>],
+        unreachable: impl IntoIterator<Item = I>,
+    ) -> Option<Downing<I>> {
+        let unreachable: BTreeSet<_> = unreachable
+            .into_iter()
+            .filter(|id| members.iter().any(|member| member.id == *id))
+            .collect();
+
+        if members != self.members || unreachable != self.unreachable {
+            self.members = members.to_vec();
+            self.unreachable = unreachable;
+            self.stable_since = None;
+            self.decided = false;
+        }
+
+        if self.unreachable.is_empty() || self.decided {
+            return None;
+        }
+
+        let now = self.clock.timestamp();
+        let elapsed = match &self.stable_since {
+            Some(stable_since) => C::elapsed(stable_since, &now),
+            None => {
+                self.stable_since = Some(now);
+                Duration::ZERO
+            }
+        };
+
+        if elapsed < self.stable_after {
+            return None;
+        }
+
+        self.decided = true;
+        Some(self.strategy.resolve(&self.members, &self.unreachable))
+    }
+}
@@ -0,0 +1,278 @@
+//! Gossip-based heartbeat dissemination, modelled after Cassandra.
+//!
+//! Instead of every node heartbeating every other node, each node increments
+//! its own heartbeat version every gossip round, and exchanges the versions it
+//! knows about all nodes with a few random peers. Whenever the version of a
+//! peer advances, a heartbeat is recorded into the peer's detector in the
+//! [`Registry`].
+//!
+//! Each round, the initiator sends digests of all known heartbeat states in a
+//! [`Message::Syn`]. The receiver responds with a [`Message::Ack`] carrying the
+//! states it has newer versions of, and digests of the states it needs, which
+//! the initiator sends in a [`Message::Ack2`].
+//!
+//! The protocol is driven by the caller: [`Gossiper::tick`] runs one gossip
+//! round, and incoming messages are passed to [`Gossiper::handle`].
+
+use {
+    crate::{rng::Rng, transport::Transport, Detector, Registry},
+    std::{
+        collections::HashMap,
+        hash::Hash,
+        sync::Arc,
+        time::{SystemTime, UNIX_EPOCH},
+    },
+};
+
+/// Heartbeat state of a node. The generation changes when the node restarts,
+/// and the version advances every gossip round.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct HeartbeatState {
+    pub generation: u64,
+    pub version: u64,
+}
+
+/// Heartbeat state of a particular node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Digest<I> {
+    pub id: I,
+    pub state: HeartbeatState,
+}
+
+/// Gossip message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Message<I> {
+    /// Digests of all heartbeat states known to the initiator.
+    Syn { digests: Vec<Digest<I>> },
+
+    /// States newer than the initiator's, and the states requested from it.
+    Ack {
+        states: Vec<Digest<I>>,
+        requests: Vec<Digest<I>>,
+    },
+
+    /// States requested by the receiver of the [`Message::Syn`].
+    Ack2 { states: Vec<Digest<I>> },
+}
+
+/// Local node of the gossip protocol.
+pub struct Gossiper<I, D, T> {
+    id: I,
+    state: HeartbeatState,
+    transport: T,
+    registry: Arc<Registry<I, D>>,
+    states: HashMap<I, Option<HeartbeatState>>,
+    // Peers in the order they have been discovered, for deterministic iteration.
+    peers: Vec<I>,
+    rng: Rng,
+    fanout: usize,
+}
+
+impl<I, D, T> Gossiper<I, D, T>
+where
+    I: Eq + Hash + Clone,
+    D: Detector,
+    T: Transport<I, Message<I>>,
+{
+    /// Creates a node without any peers, recording heartbeats into the
+    /// registry.
+    pub fn new(id: I, transport: T, registry: Arc<Registry<I, D>>) -> Self {
+        let generation = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_secs());
+
+        Self {
+            id,
+            state: HeartbeatState {
+                generation,
+                version: 0,
+            },
+            transport,
+            registry,
+            states: HashMap::new(),
+            peers: Vec::new(),
+            rng: Rng::from_entropy(),
+            fanout: 1,
+        }
+    }
+
+    /// Generation of the local node, which must increase every time the node
+    /// restarts. Defaults to the current Unix time in seconds.
+    pub fn generation(mut self, generation: u64) -> Self {
+        self.state.generation = generation;
+        self
+    }
+
+    /// Number of random peers to gossip with every round. Defaults to 1.
+    pub fn fanout(mut self, fanout: usize) -> Self {
+        self.fanout = fanout.max(1);
+        self
+    }
+
+    /// Seed of the random peer selection. Defaults to a random seed.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.rng = Rng::new(seed);
+        self
+    }
+
+    /// Returns the identifier of the local node.
+    pub fn id(&self) -> &I {
+        &self.id
+    }
+
+    /// Returns the heartbeat state of the local node.
+    pub fn state(&self) -> HeartbeatState {
+        self.state
+    }
+
+    /// Returns the most recent heartbeat state of the peer, if known.
+    pub fn peer_state(&self, id: &I) -> Option<HeartbeatState> {
+        self.states.get(id).copied().flatten()
+    }
+
+    /// Returns the registry heartbeats are recorded into.
+    pub fn registry(&self) -> &Arc<Registry<I, D>> {
+        &self.registry
+    }
+
+    /// Returns the transport used to send messages.
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    /// Adds a peer to gossip with, e.g. a seed node. Its heartbeat state is
+    /// learned with the first exchange.
+    pub fn join(&mut self, id: I) {
+        if id != self.id && !self.states.contains_key(&id) {
+            self.states.insert(id.clone(), None);
+            self.peers.push(id);
+        }
+    }
+
+    /// Advances the local heartbeat version and gossips with random peers.
+    pub fn tick(&mut self) {
+        self.state.version += 1;
+
+        let mut peers = self.peers.clone();
+        self.rng.shuffle(&mut peers);
+        peers.truncate(self.fanout);
+
+        for peer in peers {
+            let digests = self.digests();
+            self.transport.send(&peer, Message::Syn { digests });
+        }
+    }
+
+    /// Handles a message received from another node.
+    pub fn handle(&mut self, from: I, message: Message<I>) {
+        if from == self.id {
+            return;
+        }
+
+        // Receiving a message from an unknown node means it has joined.
+        self.join(from.clone());
+
+        match message {
+            Message::Syn { digests } => {
+                let mut states = Vec::new();
+                let mut requests = Vec::new();
+                let mut remote = HashMap::with_capacity(digests.len());
+
+                for digest in &digests {
+                    remote.insert(&digest.id, digest.state);
+
+                    match self.known_state(&digest.id) {
+                        Some(state) if state > digest.state => states.push(Digest {
+                            id: digest.id.clone(),
+                            state,
+                        }),
+
+                        Some(state) if state == digest.state => {}
+
+                        // Request everything newer than the known state.
+                        state => requests.push(Digest {
+                            id: digest.id.clone(),
+                            state: state.unwrap_or(HeartbeatState {
+                                generation: 0,
+                                version: 0,
+                            }),
+                        }),
+                    }
+                }
+
+                // Include the states the initiator doesn't know about.
+                states.extend(
+                    self.digests()
+                        .into_iter()
+                        .filter(|digest| !remote.contains_key(&digest.id)),
+                );
+
+                self.transport
+                    .send(&from, Message::Ack { states, requests });
+            }
+
+            Message::Ack { states, requests } => {
+                self.apply(states);
+
+                let states = requests
+                    .into_iter()
+                    .filter_map(|request| {
+                        let state = self.known_state(&request.id)?;
+                        (state > request.state).then_some(Digest {
+                            id: request.id,
+                            state,
+                        })
+                    })
+                    .collect();
+
+                self.transport.send(&from, Message::Ack2 { states });
+            }
+
+            Message::Ack2 { states } => self.apply(states),
+        }
+    }
+
+    fn known_state(&self, id: &I) -> Option<HeartbeatState> {
+        if *id == self.id {
+            Some(self.state)
+        } else {
+            self.peer_state(id)
+        }
+    }
+
+    fn digests(&self) -> Vec<Digest<I>> {
+        let peers = self.peers.iter().filter_map(|id| {
+            Some(Digest {
+                id: id.clone(),
+                state: self.peer_state(id)?,
+            })
+        });
+
+        std::iter::once(Digest {
+            id: self.id.clone(),
+            state: self.state,
+        })
+        .chain(peers)
+        .collect()
+    }
+
+    /// Records the states, heartbeating the detectors of the peers whose
+    /// heartbeat state has advanced.
+    fn apply(&mut self, states: Vec<Digest<I>>) {
+        for digest in states {
+            if digest.id == self.id {
+                continue;
+            }
+
+            self.join(digest.id.clone());
+
+            // Safe unwrap, the peer has just been added.
+            let known = self.states.get_mut(&digest.id).unwrap();
+
+            if known.is_none_or(|known| digest.state > known) {
+                *known = Some(digest.state);
+                self.registry.heartbeat(&digest.id);
+            }
+        }
+    }
+}
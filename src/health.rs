@@ -0,0 +1,132 @@
+use std::sync::{
+    atomic::{AtomicU32, Ordering},
+    Arc,
+};
+
+/// Awareness of the health of the local node, shared between detectors.
+///
+/// When the local node is slow, e.g. due to CPU starvation, heartbeats from
+/// all peers appear late and healthy peers get suspected. Based on HashiCorp's
+/// [Lifeguard](https://arxiv.org/abs/1707.00788), the score is incremented on
+/// signs of local trouble, such as missed acks or self-detected delays, and
+/// decremented on success. Detectors sharing the handle scale their threshold
+/// by `score + 1`, becoming more tolerant while the local node is unhealthy.
+///
+/// Handles are cheap to clone and share the same score, so the same handle can
+/// be passed to every detector created by a [`Registry`](crate::Registry):
+///
+/// ```
+/// use phi_accrual_failure_detector::{Detector, LocalHealth, Registry, SyncDetector};
+///
+/// let health = LocalHealth::default();
+/// let registry = Registry::<&str, _>::new({
+///     let health = health.clone();
+///     move |_| {
+///         SyncDetector::builder()
+///             .local_health(health.clone())
+///             .build()
+///             .unwrap()
+///     }
+/// });
+///
+/// registry.heartbeat(&"a");
+/// health.record_failure();
+/// assert_eq!(
+///     registry.get(&"a").unwrap().stats().effective_threshold,
+///     16.0
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct LocalHealth(Arc<Inner>);
+
+#[derive(Debug)]
+struct Inner {
+    score: AtomicU32,
+    max_score: u32,
+}
+
+impl LocalHealth {
+    /// Creates a healthy instance with the score saturating at `max_score`.
+    pub fn new(max_score: u32) -> Self {
+        Self(Arc::new(Inner {
+            score: AtomicU32::new(0),
+            max_score,
+        }))
+    }
+
+    /// Returns the current score, `0` being healthy.
+    pub fn score(&self) -> u32 {
+        self.0.score.load(Ordering::Relaxed)
+    }
+
+    /// Returns the maximum score.
+    pub fn max_score(&self) -> u32 {
+        self.0.max_score
+    }
+
+    /// Returns the factor detectors scale their threshold by, i.e. `score + 1`.
+    pub fn multiplier(&self) -> f64 {
+        (self.score() + 1) as f64
+    }
+
+    /// Adjusts the score by `delta`, clamping it to `[0, max_score]`.
+    pub fn adjust(&self, delta: i32) {
+        let max_score = self.0.max_score;
+
+        // Safe unwrap, the closure always returns `Some`.
+        self.0
+            .score
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |score| {
+                Some(score.saturating_add_signed(delta).min(max_score))
+            })
+            .unwrap();
+    }
+
+    /// Records a sign of local trouble, e.g. a missed ack or a local delay.
+    pub fn record_failure(&self) {
+        self.adjust(1);
+    }
+
+    /// Records a successful interaction, e.g. a received ack.
+    pub fn record_success(&self) {
+        self.adjust(-1);
+    }
+
+    /// Resets the score to healthy.
+    pub fn reset(&self) {
+        self.0.score.store(0, Ordering::Relaxed);
+    }
+}
+
+impl Default for LocalHealth {
+    /// Creates an instance with the maximum score of 8, as used by Lifeguard.
+    fn default() -> Self {
+        Self::new(8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn score_clamped() {
+        let health = LocalHealth::new(3);
+        let shared = health.clone();
+
+        health.record_success();
+        assert_eq!(shared.score(), 0);
+
+        for _ in 0..5 {
+            health.record_failure();
+        }
+        assert_eq!(shared.score(), 3);
+        assert_eq!(shared.multiplier(), 4.0);
+
+        health.adjust(-2);
+        assert_eq!(shared.score(), 1);
+
+        health.reset();
+        assert_eq!(shared.score(), 0);
+    }
+}
@@ -0,0 +1,98 @@
+use std::time::Duration;
+
+/// Distribution of heartbeat inter-arrival times bucketed by configurable
+/// bounds.
+///
+/// Each bucket counts the intervals that are less than or equal to its upper
+/// bound and greater than the upper bound of the previous bucket. The last
+/// bucket is unbounded and counts the remaining intervals.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Histogram {
+    buckets: Vec<Bucket>,
+}
+
+/// A single [`Histogram`] bucket.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bucket {
+    /// Inclusive upper bound of the bucket. `None` for the last, unbounded
+    /// bucket.
+    pub upper_bound: Option<Duration>,
+
+    /// Number of intervals that fall into the bucket.
+    pub count: usize,
+}
+
+impl Histogram {
+    /// Builds a histogram of the `intervals` using the provided bucket
+    /// `bounds`. The bounds don't have to be sorted, and duplicates are
+    /// ignored.
+    pub fn new(bounds: &[Duration], intervals: impl IntoIterator<Item = Duration>) -> Self {
+        let mut bounds = bounds.to_vec();
+        bounds.sort_unstable();
+        bounds.dedup();
+
+        let mut buckets: Vec<_> = bounds
+            .into_iter()
+            .map(Some)
+            .chain([None])
+            .map(|upper_bound| Bucket {
+                upper_bound,
+                count: 0,
+            })
+            .collect();
+
+        for interval in intervals {
+            let idx = buckets
+                .iter()
+                .position(|bucket| bucket.upper_bound.is_none_or(|bound| interval <= bound))
+                .unwrap_or(buckets.len() - 1);
+
+            buckets[idx].count += 1;
+        }
+
+        Self { buckets }
+    }
+
+    /// Returns the buckets ordered by their upper bound.
+    pub fn buckets(&self) -> &[Bucket] {
+        &self.buckets
+    }
+
+    /// Total number of intervals in the histogram.
+    pub fn total(&self) -> usize {
+        self.buckets.iter().map(|bucket| bucket.count).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bucketing() {
+        let ms = Duration::from_millis;
+        let histogram = Histogram::new(&[ms(200), ms(100), ms(100)], [
+            ms(50),
+            ms(100),
+            ms(150),
+            ms(250),
+            ms(1000),
+        ]);
+
+        assert_eq!(histogram.buckets(), &[
+            Bucket {
+                upper_bound: Some(ms(100)),
+                count: 2,
+            },
+            Bucket {
+                upper_bound: Some(ms(200)),
+                count: 1,
+            },
+            Bucket {
+                upper_bound: None,
+                count: 2,
+            },
+        ]);
+        assert_eq!(histogram.total(), 5);
+    }
+}
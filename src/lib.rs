@@ -1,10 +1,50 @@
+#[cfg(any(feature = "metrics", feature = "tracing"))]
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::{
     cell::RefCell,
     marker::PhantomData,
-    sync::RwLock,
+    sync::{Arc, RwLock},
     time::{Duration, Instant},
 };
 
+#[cfg(feature = "tokio")]
+pub use clock::TokioClock;
+pub use {
+    clock::{CoarseClock, ManualClock, SystemClock},
+    health::LocalHealth,
+    histogram::{Bucket, Histogram},
+    reachability::{Reachability, ReachabilityRecord, ReachabilityStatus},
+    registry::Registry,
+    scheduler::{HeartbeatScheduler, SchedulerStats},
+    watchdog::Watchdog,
+};
+
+mod clock;
+pub mod downing;
+pub mod gossip;
+mod health;
+mod histogram;
+#[cfg(feature = "metrics")]
+mod metrics;
+#[cfg(feature = "net")]
+pub mod net;
+pub mod prometheus;
+pub mod qos;
+pub mod quorum;
+mod reachability;
+mod registry;
+pub mod replay;
+mod rng;
+mod scheduler;
+#[cfg(feature = "sim")]
+pub mod sim;
+pub mod stream;
+pub mod sweep;
+pub mod swim;
+pub mod transport;
+mod watchdog;
+pub mod wire;
+
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error("Threshold must be > 0")]
@@ -29,6 +69,8 @@ pub type SyncDetector = FailureDetector<SyncState<DefaultClock>>;
 /// [`FailureDetector`] builder.
 pub struct Builder<S: sealed::State> {
     config: Config,
+    name: Option<Arc<str>>,
+    local_health: Option<LocalHealth>,
     clock: S::Clock,
     _marker: PhantomData<S>,
 }
@@ -37,6 +79,8 @@ impl<S: sealed::State<Clock = DefaultClock>> Builder<S> {
     pub fn new() -> Self {
         Self {
             config: Default::default(),
+            name: None,
+            local_health: None,
             clock: DefaultClock,
             _marker: PhantomData,
         }
@@ -105,6 +149,44 @@ impl<S: sealed::State> Builder<S> {
         self
     }
 
+    /// Name of the monitored resource, e.g. peer address.
+    ///
+    /// With the `metrics` feature enabled, named detectors report their state
+    /// using the [`metrics`](https://docs.rs/metrics) facade, labelled with
+    /// `peer=<name>`. The metrics recorder must be installed before the
+    /// detector is built.
+    ///
+    /// The metrics are poll-driven: the phi gauge and the suspicion and
+    /// recovery counters are only updated when the detector receives a
+    /// heartbeat or is queried with [`Detector::phi`],
+    /// [`Detector::is_available`] or [`Detector::status`]. A detector that
+    /// nobody queries never counts a suspicion, so it should be polled
+    /// periodically, e.g. before each metrics scrape.
+    ///
+    /// With the `tracing` feature enabled, the name is recorded as the `peer`
+    /// field of emitted events.
+    ///
+    /// Default: None
+    pub fn name(mut self, name: impl Into<Arc<str>>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Replaces all configuration parameters at once, e.g. with a [`Config`]
+    /// loaded from a file or found by parameter tuning.
+    pub fn config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Local health awareness scaling the threshold, see [`LocalHealth`].
+    ///
+    /// Default: None
+    pub fn local_health(mut self, local_health: LocalHealth) -> Self {
+        self.local_health = Some(local_health);
+        self
+    }
+
     /// Use [`RwLock`] internally to make the detector [`Sync`].
     pub fn sync(self) -> Builder<SyncState<S::Clock>> {
         self.state::<SyncState<S::Clock>>()
@@ -122,6 +204,8 @@ impl<S: sealed::State> Builder<S> {
     pub fn clock<T: Clock>(self, clock: T) -> Builder<S::WithClock<T>> {
         Builder {
             config: self.config,
+            name: self.name,
+            local_health: self.local_health,
             clock,
             _marker: PhantomData,
         }
@@ -161,14 +245,28 @@ impl<S: sealed::State> Builder<S> {
         history.add(mean + std_deviation);
 
         let state = DetectorState {
+            // Only needed in the state for events emitted while updating it.
+            #[cfg(feature = "tracing")]
+            name: self.name.clone(),
             threshold,
+            local_health: self.local_health,
             acceptable_heartbeat_pause,
             min_std_deviation,
             history,
             last_timestamp: None,
+            last_interval: None,
+            heartbeat_count: 0,
+            paused: false,
+            skip_next_interval: false,
+            excluded: 0.,
         };
 
         Ok(FailureDetector {
+            #[cfg(any(feature = "metrics", feature = "tracing"))]
+            available: AtomicBool::new(true),
+            #[cfg(feature = "metrics")]
+            metrics: self.name.as_deref().map(metrics::Metrics::new),
+            name: self.name,
             state: state.into(),
             clock: self.clock,
         })
@@ -177,18 +275,23 @@ impl<S: sealed::State> Builder<S> {
     fn state<T: sealed::State<Clock = S::Clock>>(self) -> Builder<T> {
         Builder {
             config: self.config,
+            name: self.name,
+            local_health: self.local_health,
             clock: self.clock,
             _marker: PhantomData,
         }
     }
 }
 
-struct Config {
-    threshold: f64,
-    max_sample_size: usize,
-    min_std_deviation: Duration,
-    acceptable_heartbeat_pause: Duration,
-    first_heartbeat_estimate: Duration,
+/// Detector configuration parameters, see the [`Builder`] methods of the same
+/// names for details.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    pub threshold: f64,
+    pub max_sample_size: usize,
+    pub min_std_deviation: Duration,
+    pub acceptable_heartbeat_pause: Duration,
+    pub first_heartbeat_estimate: Duration,
 }
 
 impl Default for Config {
@@ -204,36 +307,186 @@ impl Default for Config {
 }
 
 struct DetectorState<C: Clock> {
+    #[cfg(feature = "tracing")]
+    name: Option<Arc<str>>,
     threshold: f64,
+    local_health: Option<LocalHealth>,
     acceptable_heartbeat_pause: f64,
     min_std_deviation: f64,
     history: HeartbeatHistory,
     last_timestamp: Option<C::Timestamp>,
+    last_interval: Option<f64>,
+    heartbeat_count: u64,
+    paused: bool,
+    skip_next_interval: bool,
+    excluded: f64,
 }
 
 impl<C: Clock> DetectorState<C> {
-    fn heartbeat(&mut self, timestamp: C::Timestamp) {
-        if let (Some(last_timestamp), true) = (
-            &self.last_timestamp,
-            self.is_available_for_timestamp(&timestamp),
-        ) {
-            self.history.add(C::elapsed_ms(last_timestamp, &timestamp));
+    /// Returns the interval recorded into the history, if any.
+    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+    fn heartbeat(&mut self, timestamp: C::Timestamp) -> Option<f64> {
+        // Intervals spanning a monitoring pause are not representative of the
+        // heartbeat distribution, so they're not recorded.
+        let discard_reason = if self.paused {
+            Some("paused")
+        } else if std::mem::take(&mut self.skip_next_interval) {
+            Some("resumed")
+        } else if !self.is_available_for_timestamp(&timestamp) {
+            Some("unavailable")
+        } else {
+            None
+        };
+
+        let mut recorded = None;
+        let excluded = std::mem::take(&mut self.excluded);
+
+        if let Some(last_timestamp) = &self.last_timestamp {
+            let interval = (C::elapsed_ms(last_timestamp, &timestamp) - excluded).max(0.);
+
+            if let Some(reason) = discard_reason {
+                #[cfg(feature = "tracing")]
+                tracing::debug!(
+                    peer = self.name.as_deref(),
+                    interval_ms = interval,
+                    reason,
+                    "heartbeat interval discarded"
+                );
+            } else {
+                self.history.add(interval);
+                recorded = Some(interval);
+            }
+
+            self.last_interval = Some(interval);
         }
 
         self.last_timestamp = Some(timestamp);
+        self.heartbeat_count += 1;
+
+        recorded
+    }
+
+    fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    fn resume(&mut self, timestamp: C::Timestamp) {
+        if !self.paused {
+            return;
+        }
+
+        self.paused = false;
+
+        // Measure the time since last heartbeat from the moment of resumption, and
+        // discard the interval that covers the pause.
+        if self.last_timestamp.is_some() {
+            self.last_timestamp = Some(timestamp);
+            self.skip_next_interval = true;
+            self.excluded = 0.;
+        }
+    }
+
+    fn exclude(&mut self, duration: Duration, timestamp: &C::Timestamp) {
+        // Stalls before the first heartbeat or during a pause don't affect phi.
+        let Some(last_timestamp) = &self.last_timestamp else {
+            return;
+        };
+
+        if !self.paused {
+            // The stall has just ended, so only the part of it since the last
+            // heartbeat overlaps the current interval.
+            let elapsed = C::elapsed_ms(last_timestamp, timestamp);
+            self.excluded += (duration.as_secs_f64() * 1000.).min(elapsed);
+        }
+    }
+
+    fn stats_for_timestamp(&self, timestamp: &C::Timestamp) -> DetectorStats {
+        let std_deviation = self.history.std_deviation();
+        let phi = self.phi_for_timestamp(timestamp);
+
+        DetectorStats {
+            heartbeat_count: self.heartbeat_count,
+            sample_count: self.history.sample_count(),
+            mean: duration_from_ms(self.history.mean()),
+            std_deviation: duration_from_ms(std_deviation),
+            clamped_std_deviation: duration_from_ms(std_deviation.max(self.min_std_deviation)),
+            last_interval: self.last_interval.map(duration_from_ms),
+            time_since_last_heartbeat: self
+                .last_timestamp
+                .as_ref()
+                .map(|last_timestamp| C::elapsed(last_timestamp, timestamp)),
+            phi,
+            status: self.status_for_phi(phi),
+            threshold: self.threshold,
+            effective_threshold: self.effective_threshold(),
+            acceptable_heartbeat_pause: duration_from_ms(self.acceptable_heartbeat_pause),
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    fn trace_transition(&self, timestamp: &C::Timestamp, phi: f64, available: bool) {
+        let peer = self.name.as_deref();
+        let interval_mean_ms = self.history.mean();
+        let interval_std_deviation_ms = self.history.std_deviation();
+        let elapsed_ms = self
+            .last_timestamp
+            .as_ref()
+            .map(|last_timestamp| C::elapsed_ms(last_timestamp, timestamp));
+
+        if available {
+            tracing::info!(
+                peer,
+                phi,
+                interval_mean_ms,
+                interval_std_deviation_ms,
+                elapsed_ms,
+                "resource recovered"
+            );
+        } else {
+            tracing::warn!(
+                peer,
+                phi,
+                interval_mean_ms,
+                interval_std_deviation_ms,
+                elapsed_ms,
+                "resource suspected"
+            );
+        }
+    }
+
+    fn status_for_phi(&self, phi: f64) -> Status {
+        if self.paused {
+            Status::Paused
+        } else if phi < self.effective_threshold() {
+            Status::Available
+        } else {
+            Status::Unavailable
+        }
     }
 
     fn is_available_for_timestamp(&self, timestamp: &C::Timestamp) -> bool {
-        self.phi_for_timestamp(timestamp) < self.threshold
+        self.phi_for_timestamp(timestamp) < self.effective_threshold()
+    }
+
+    /// Returns the threshold scaled by the local health multiplier.
+    fn effective_threshold(&self) -> f64 {
+        match &self.local_health {
+            Some(local_health) => self.threshold * local_health.multiplier(),
+            None => self.threshold,
+        }
     }
 
     fn phi_for_timestamp(&self, timestamp: &C::Timestamp) -> f64 {
+        if self.paused {
+            return 0.0;
+        }
+
         let Some(last_timestamp) = &self.last_timestamp else {
             // No heartbeats received yet.
             return 0.0;
         };
 
-        let time_diff = C::elapsed_ms(last_timestamp, timestamp);
+        let time_diff = (C::elapsed_ms(last_timestamp, timestamp) - self.excluded).max(0.);
         let mean = self.history.mean() + self.acceptable_heartbeat_pause;
         let std_deviation = self.history.std_deviation().max(self.min_std_deviation);
 
@@ -261,8 +514,13 @@ impl<C: Clock> DetectorState<C> {
 /// with mean and standard deviation estimated from historical heartbeat
 /// inter-arrival times.
 pub struct FailureDetector<S: sealed::State> {
+    name: Option<Arc<str>>,
     state: S,
     clock: S::Clock,
+    #[cfg(any(feature = "metrics", feature = "tracing"))]
+    available: AtomicBool,
+    #[cfg(feature = "metrics")]
+    metrics: Option<metrics::Metrics>,
 }
 
 impl<S: sealed::State<Clock = DefaultClock>> FailureDetector<S> {
@@ -271,6 +529,71 @@ impl<S: sealed::State<Clock = DefaultClock>> FailureDetector<S> {
     }
 }
 
+impl<S: sealed::State> FailureDetector<S> {
+    /// Name of the monitored resource, as configured with [`Builder::name`].
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Calculates current phi and status of the monitored resource.
+    fn evaluate(&self) -> (f64, Status) {
+        let timestamp = self.clock.timestamp();
+
+        self.state.read(|state| {
+            let phi = state.phi_for_timestamp(&timestamp);
+            let status = state.status_for_phi(phi);
+
+            self.on_evaluate(state, &timestamp, phi, status);
+
+            (phi, status)
+        })
+    }
+
+    #[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+    fn on_heartbeat(&self, state: &DetectorState<S::Clock>, interval: Option<f64>) {
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = &self.metrics {
+            metrics.heartbeat(interval, &state.history);
+        }
+
+        // Phi is reset by the heartbeat.
+        if let Some(timestamp) = &state.last_timestamp {
+            self.on_evaluate(state, timestamp, 0.0, state.status_for_phi(0.0));
+        }
+    }
+
+    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+    fn on_evaluate(
+        &self,
+        state: &DetectorState<S::Clock>,
+        timestamp: &<S::Clock as Clock>::Timestamp,
+        phi: f64,
+        status: Status,
+    ) {
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = &self.metrics {
+            metrics.phi(phi);
+        }
+
+        #[cfg(any(feature = "metrics", feature = "tracing"))]
+        {
+            let available = status != Status::Unavailable;
+
+            if self.available.swap(available, Ordering::Relaxed) == available {
+                return;
+            }
+
+            #[cfg(feature = "metrics")]
+            if let Some(metrics) = &self.metrics {
+                metrics.transition(available);
+            }
+
+            #[cfg(feature = "tracing")]
+            state.trace_transition(timestamp, phi, available);
+        }
+    }
+}
+
 impl<S: sealed::State<Clock = DefaultClock>> Default for FailureDetector<S> {
     fn default() -> Self {
         // Safe unwrap with default parameters.
@@ -286,87 +609,328 @@ pub trait Detector {
     /// The suspicion level of the accrual failure detector.
     ///
     /// If a connection does not have any records in failure detector then it is
-    /// considered healthy.
+    /// considered healthy. Paused detectors always report `0.0`.
     fn phi(&self) -> f64;
 
     /// Returns `true` if the resource is considered to be up and healthy and
     /// returns `false` otherwise.
     fn is_available(&self) -> bool;
+
+    /// Returns the current [`Status`] of the monitored resource.
+    ///
+    /// The default implementation derives it from [`Detector::is_available`].
+    fn status(&self) -> Status {
+        if self.is_available() {
+            Status::Available
+        } else {
+            Status::Unavailable
+        }
+    }
+
+    /// Suspends monitoring of the resource, e.g. for the duration of a planned
+    /// maintenance. A paused detector never suspects the resource and reports
+    /// [`Status::Paused`].
+    ///
+    /// The default implementation does nothing, i.e. pausing is not supported.
+    fn pause_monitoring(&self) {}
+
+    /// Resumes monitoring of the resource after [`Detector::pause_monitoring`].
+    ///
+    /// The time since last heartbeat is measured from the moment monitoring is
+    /// resumed, and the next heartbeat interval is not recorded into the
+    /// history, so that the pause does not skew the learned distribution.
+    ///
+    /// The default implementation does nothing.
+    fn resume_monitoring(&self) {}
+
+    /// Excludes a local stall of the given duration, e.g. detected by a
+    /// [`Watchdog`], from the time since last heartbeat.
+    ///
+    /// The excluded time does not count towards phi, and is subtracted from the
+    /// next heartbeat interval before it's recorded into the history. The stall
+    /// is assumed to have just ended, so only the part of it after the last
+    /// heartbeat is excluded.
+    ///
+    /// The default implementation does nothing.
+    fn exclude_stall(&self, duration: Duration) {
+        let _ = duration;
+    }
+
+    /// Returns a snapshot of the statistics learned by the detector.
+    ///
+    /// The default implementation only reports the current phi and status,
+    /// with empty statistics and NaN thresholds.
+    fn stats(&self) -> DetectorStats {
+        DetectorStats {
+            heartbeat_count: 0,
+            sample_count: 0,
+            mean: Duration::ZERO,
+            std_deviation: Duration::ZERO,
+            clamped_std_deviation: Duration::ZERO,
+            last_interval: None,
+            time_since_last_heartbeat: None,
+            phi: self.phi(),
+            status: self.status(),
+            threshold: f64::NAN,
+            effective_threshold: f64::NAN,
+            acceptable_heartbeat_pause: Duration::ZERO,
+        }
+    }
+
+    /// Returns the inter-arrival times retained in the heartbeat history, from
+    /// the oldest to the most recent. This includes the samples derived from
+    /// the first heartbeat estimate, until they're evicted from the history.
+    ///
+    /// The default implementation returns an empty history.
+    fn history_iter(&self) -> HistoryIter {
+        HistoryIter::from(Vec::new())
+    }
+
+    /// Returns a [`Histogram`] of the inter-arrival times retained in the
+    /// heartbeat history, bucketed by the provided upper `bounds`.
+    fn histogram(&self, bounds: &[Duration]) -> Histogram {
+        Histogram::new(bounds, self.history_iter())
+    }
 }
 
-/// A [`FailureDetector`] state wrapper based on [`RefCell`] for single-threaded
-/// access.
-pub struct UnsyncState<C: Clock>(RefCell<DetectorState<C>>);
+/// Availability status of the monitored resource.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    /// The resource is considered to be up and healthy.
+    Available,
 
-impl<C: Clock> sealed::State for UnsyncState<C> {
-    type Clock = C;
-    type WithClock<T: Clock> = UnsyncState<T>;
+    /// The resource is suspected to have failed.
+    Unavailable,
+
+    /// Monitoring of the resource is paused.
+    Paused,
 }
 
-impl<C: Clock> From<DetectorState<C>> for UnsyncState<C> {
-    fn from(inner: DetectorState<C>) -> Self {
-        Self(RefCell::new(inner))
+/// Snapshot of the statistics learned by a [`Detector`], e.g. for debugging
+/// false suspicions or exporting as metrics.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DetectorStats {
+    /// Total number of heartbeats received.
+    pub heartbeat_count: u64,
+
+    /// Number of inter-arrival samples the stats are calculated from, including
+    /// the samples derived from the first heartbeat estimate.
+    pub sample_count: usize,
+
+    /// Mean heartbeat inter-arrival time.
+    pub mean: Duration,
+
+    /// Standard deviation of heartbeat inter-arrival times.
+    pub std_deviation: Duration,
+
+    /// Standard deviation used when calculating phi, i.e. clamped by the
+    /// configured minimum standard deviation.
+    pub clamped_std_deviation: Duration,
+
+    /// Interval between the two most recent heartbeats, regardless of whether
+    /// it was recorded into the history.
+    pub last_interval: Option<Duration>,
+
+    /// Time elapsed since the most recent heartbeat.
+    pub time_since_last_heartbeat: Option<Duration>,
+
+    /// Current suspicion level.
+    pub phi: f64,
+
+    /// Current status of the monitored resource.
+    pub status: Status,
+
+    /// Configured suspicion threshold, NaN if the detector doesn't report it.
+    pub threshold: f64,
+
+    /// Threshold the status is decided by, i.e. the configured threshold scaled
+    /// by the [`LocalHealth`] multiplier, or NaN if the detector doesn't
+    /// report it.
+    pub effective_threshold: f64,
+
+    /// Configured acceptable heartbeat pause. The normal distribution used for
+    /// calculating phi is centered at `mean + acceptable_heartbeat_pause`.
+    pub acceptable_heartbeat_pause: Duration,
+}
+
+/// Iterator over the inter-arrival times retained in the heartbeat history.
+///
+/// Returned by [`Detector::history_iter`]. Holds a snapshot of the history, so
+/// it does not block heartbeats while being consumed. Custom detectors create
+/// it from their intervals, from the oldest to the most recent.
+pub struct HistoryIter(std::vec::IntoIter<Duration>);
+
+impl From<Vec<Duration>> for HistoryIter {
+    fn from(intervals: Vec<Duration>) -> Self {
+        Self(intervals.into_iter())
     }
 }
 
-impl<C: Clock> Detector for FailureDetector<UnsyncState<C>> {
+impl Iterator for HistoryIter {
+    type Item = Duration;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl ExactSizeIterator for HistoryIter {}
+
+macro_rules! forward_detector {
+    ($ty:ty) => {
+        impl<D: Detector + ?Sized> Detector for $ty {
+            fn heartbeat(&self) {
+                (**self).heartbeat()
+            }
+
+            fn phi(&self) -> f64 {
+                (**self).phi()
+            }
+
+            fn is_available(&self) -> bool {
+                (**self).is_available()
+            }
+
+            fn status(&self) -> Status {
+                (**self).status()
+            }
+
+            fn pause_monitoring(&self) {
+                (**self).pause_monitoring()
+            }
+
+            fn resume_monitoring(&self) {
+                (**self).resume_monitoring()
+            }
+
+            fn exclude_stall(&self, duration: Duration) {
+                (**self).exclude_stall(duration)
+            }
+
+            fn stats(&self) -> DetectorStats {
+                (**self).stats()
+            }
+
+            fn history_iter(&self) -> HistoryIter {
+                (**self).history_iter()
+            }
+        }
+    };
+}
+
+forward_detector!(&D);
+forward_detector!(Box<D>);
+forward_detector!(Arc<D>);
+
+impl<S: sealed::State> Detector for FailureDetector<S> {
     fn heartbeat(&self) {
-        self.state.0.borrow_mut().heartbeat(self.clock.timestamp());
+        let timestamp = self.clock.timestamp();
+
+        self.state.write(|state| {
+            let interval = state.heartbeat(timestamp);
+            self.on_heartbeat(state, interval);
+        });
     }
 
     fn phi(&self) -> f64 {
-        self.state
-            .0
-            .borrow()
-            .phi_for_timestamp(&self.clock.timestamp())
+        self.evaluate().0
     }
 
     fn is_available(&self) -> bool {
+        self.evaluate().1 != Status::Unavailable
+    }
+
+    fn status(&self) -> Status {
+        self.evaluate().1
+    }
+
+    fn pause_monitoring(&self) {
+        self.state.write(|state| state.pause());
+    }
+
+    fn resume_monitoring(&self) {
+        let timestamp = self.clock.timestamp();
+        self.state.write(|state| state.resume(timestamp));
+    }
+
+    fn exclude_stall(&self, duration: Duration) {
+        let timestamp = self.clock.timestamp();
+        self.state
+            .write(|state| state.exclude(duration, &timestamp));
+    }
+
+    fn stats(&self) -> DetectorStats {
+        let timestamp = self.clock.timestamp();
         self.state
-            .0
-            .borrow()
-            .is_available_for_timestamp(&self.clock.timestamp())
+            .read(|state| state.stats_for_timestamp(&timestamp))
+    }
+
+    fn history_iter(&self) -> HistoryIter {
+        let intervals = self.state.read(|state| {
+            state
+                .history
+                .intervals
+                .iter()
+                .copied()
+                .map(duration_from_ms)
+                .collect::<Vec<_>>()
+        });
+
+        HistoryIter::from(intervals)
     }
 }
 
-/// A [`FailureDetector`] state wrapper based on [`RwLock`] for multi-threaded
+/// A [`FailureDetector`] state wrapper based on [`RefCell`] for single-threaded
 /// access.
-pub struct SyncState<C: Clock>(RwLock<DetectorState<C>>);
+pub struct UnsyncState<C: Clock>(RefCell<DetectorState<C>>);
 
-impl<C: Clock> sealed::State for SyncState<C> {
+impl<C: Clock> sealed::State for UnsyncState<C> {
     type Clock = C;
-    type WithClock<T: Clock> = SyncState<T>;
+    type WithClock<T: Clock> = UnsyncState<T>;
+
+    #[allow(private_bounds)]
+    fn read<R>(&self, f: impl FnOnce(&DetectorState<C>) -> R) -> R {
+        f(&self.0.borrow())
+    }
+
+    #[allow(private_bounds)]
+    fn write<R>(&self, f: impl FnOnce(&mut DetectorState<C>) -> R) -> R {
+        f(&mut self.0.borrow_mut())
+    }
 }
 
-impl<C: Clock> From<DetectorState<C>> for SyncState<C> {
+impl<C: Clock> From<DetectorState<C>> for UnsyncState<C> {
     fn from(inner: DetectorState<C>) -> Self {
-        Self(RwLock::new(inner))
+        Self(RefCell::new(inner))
     }
 }
 
-impl<C: Clock> Detector for FailureDetector<SyncState<C>> {
-    fn heartbeat(&self) {
-        self.state
-            .0
-            .write()
-            .unwrap()
-            .heartbeat(self.clock.timestamp());
+/// A [`FailureDetector`] state wrapper based on [`RwLock`] for multi-threaded
+/// access.
+pub struct SyncState<C: Clock>(RwLock<DetectorState<C>>);
+
+impl<C: Clock> sealed::State for SyncState<C> {
+    type Clock = C;
+    type WithClock<T: Clock> = SyncState<T>;
+
+    #[allow(private_bounds)]
+    fn read<R>(&self, f: impl FnOnce(&DetectorState<C>) -> R) -> R {
+        f(&self.0.read().unwrap())
     }
 
-    fn phi(&self) -> f64 {
-        self.state
-            .0
-            .read()
-            .unwrap()
-            .phi_for_timestamp(&self.clock.timestamp())
+    #[allow(private_bounds)]
+    fn write<R>(&self, f: impl FnOnce(&mut DetectorState<C>) -> R) -> R {
+        f(&mut self.0.write().unwrap())
     }
+}
 
-    fn is_available(&self) -> bool {
-        self.state
-            .0
-            .read()
-            .unwrap()
-            .is_available_for_timestamp(&self.clock.timestamp())
+impl<C: Clock> From<DetectorState<C>> for SyncState<C> {
+    fn from(inner: DetectorState<C>) -> Self {
+        Self(RwLock::new(inner))
     }
 }
 
@@ -377,6 +941,10 @@ mod sealed {
     pub trait State: From<DetectorState<Self::Clock>> {
         type Clock: Clock;
         type WithClock<T: Clock>: State<Clock = T>;
+
+        fn read<R>(&self, f: impl FnOnce(&DetectorState<Self::Clock>) -> R) -> R;
+
+        fn write<R>(&self, f: impl FnOnce(&mut DetectorState<Self::Clock>) -> R) -> R;
     }
 }
 
@@ -435,12 +1003,16 @@ impl HeartbeatHistory {
         }
     }
 
+    fn sample_count(&self) -> usize {
+        self.intervals.size()
+    }
+
     fn mean(&self) -> f64 {
-        self.interval_sum / self.intervals.len() as f64
+        self.interval_sum / self.sample_count() as f64
     }
 
     fn variance(&self) -> f64 {
-        self.squared_interval_sum / self.intervals.len() as f64 - pow2(self.mean())
+        self.squared_interval_sum / self.sample_count() as f64 - pow2(self.mean())
     }
 
     fn std_deviation(&self) -> f64 {
@@ -463,6 +1035,12 @@ fn pow2(x: f64) -> f64 {
     x * x
 }
 
+/// Converts milliseconds to [`Duration`], treating negative and `NaN` values
+/// (which may result from floating point errors) as zero.
+fn duration_from_ms(ms: f64) -> Duration {
+    Duration::try_from_secs_f64(ms.max(0.) / 1000.).unwrap_or(Duration::MAX)
+}
+
 /// Simple circular buffer that only allows for pushing values, and returns the
 /// oldest value on overflow.
 #[derive(Clone)]
@@ -496,9 +1074,21 @@ impl<T> CircleBuffer<T> {
         }
     }
 
+    #[cfg(test)]
     fn len(&self) -> usize {
         self.cursor
     }
+
+    /// Number of values currently stored in the buffer.
+    fn size(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Iterates over the stored values from the oldest to the most recent.
+    fn iter(&self) -> impl Iterator<Item = &T> {
+        let (newest, oldest) = self.data.split_at(self.cursor % self.capacity);
+        oldest.iter().chain(newest)
+    }
 }
 
 #[cfg(test)]
@@ -524,6 +1114,39 @@ mod tests {
         assert_eq!(buf.len(), 6);
         assert_eq!(buf.push(7), Some(4));
         assert_eq!(buf.len(), 7);
+        assert_eq!(buf.iter().copied().collect::<Vec<_>>(), vec![5, 6, 7]);
+    }
+
+    #[test]
+    fn resume_skips_paused_interval() {
+        let detector = UnsyncDetector::default();
+
+        detector.heartbeat();
+        detector.heartbeat();
+        detector.pause_monitoring();
+        detector.heartbeat();
+        detector.resume_monitoring();
+        detector.heartbeat();
+
+        // Only the interval between the first two heartbeats has been recorded,
+        // in addition to the two bootstrap samples.
+        assert_eq!(detector.state.0.borrow().history.intervals.len(), 3);
+
+        detector.heartbeat();
+        assert_eq!(detector.state.0.borrow().history.intervals.len(), 4);
+    }
+
+    #[test]
+    fn history_stats_capped() {
+        let mut history = HeartbeatHistory::new(3);
+
+        for interval in [100., 100., 100., 200., 200., 200.] {
+            history.add(interval);
+        }
+
+        assert_eq!(history.sample_count(), 3);
+        assert_eq!(history.mean(), 200.);
+        assert_eq!(history.variance(), 0.);
     }
 
     fn ensure_sync<T: Sync>() {}
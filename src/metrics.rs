@@ -0,0 +1,57 @@
+use {
+    crate::HeartbeatHistory,
+    ::metrics::{counter, gauge, histogram, Counter, Gauge, Histogram},
+};
+
+/// Metrics reported by a named [`FailureDetector`](crate::FailureDetector).
+///
+/// Updated on heartbeats and queries only, the detector doesn't poll itself.
+pub(crate) struct Metrics {
+    phi: Gauge,
+    interval_mean: Gauge,
+    interval_std_deviation: Gauge,
+    intervals: Histogram,
+    heartbeats: Counter,
+    suspicions: Counter,
+    recoveries: Counter,
+}
+
+impl Metrics {
+    pub fn new(name: &str) -> Self {
+        let labels = [("peer", name.to_owned())];
+
+        Self {
+            phi: gauge!("phi_accrual_phi", &labels),
+            interval_mean: gauge!("phi_accrual_interval_mean_seconds", &labels),
+            interval_std_deviation: gauge!("phi_accrual_interval_std_deviation_seconds", &labels),
+            intervals: histogram!("phi_accrual_interval_seconds", &labels),
+            heartbeats: counter!("phi_accrual_heartbeats_total", &labels),
+            suspicions: counter!("phi_accrual_suspicions_total", &labels),
+            recoveries: counter!("phi_accrual_recoveries_total", &labels),
+        }
+    }
+
+    pub fn heartbeat(&self, interval: Option<f64>, history: &HeartbeatHistory) {
+        self.heartbeats.increment(1);
+
+        if let Some(interval) = interval {
+            self.intervals.record(interval / 1000.);
+        }
+
+        self.interval_mean.set(history.mean() / 1000.);
+        self.interval_std_deviation
+            .set(history.std_deviation() / 1000.);
+    }
+
+    pub fn phi(&self, phi: f64) {
+        self.phi.set(phi);
+    }
+
+    pub fn transition(&self, available: bool) {
+        if available {
+            self.recoveries.increment(1);
+        } else {
+            self.suspicions.increment(1);
+        }
+    }
+}
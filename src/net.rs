@@ -0,0 +1,370 @@
+//! UDP heartbeat transport.
+//!
+//! [`HeartbeatSender`] periodically sends [`Frame`]s to a set of targets, and
+//! [`HeartbeatReceiver`] decodes them and records heartbeats into a
+//! [`Registry`] keyed by sender node ID.
+
+use {
+    crate::{
+        wire::{Frame, HEADER_LEN},
+        Detector,
+        HeartbeatScheduler,
+        Registry,
+    },
+    std::{
+        collections::HashMap,
+        fmt,
+        io,
+        net::{SocketAddr, ToSocketAddrs, UdpSocket},
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+            Mutex,
+        },
+        thread::{self, JoinHandle},
+        time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+    },
+};
+
+/// Maximum payload of a UDP datagram over IPv4, i.e. maximum length of a frame
+/// that can be sent in one datagram.
+pub const MAX_DATAGRAM_LEN: usize = 65_507;
+
+/// Error returned when a heartbeat couldn't be sent to some of the targets.
+///
+/// Wrapped into the [`io::Error`] returned by [`HeartbeatSender::send`], with
+/// the kind of the first failure.
+#[derive(Debug)]
+pub struct SendError {
+    /// Sequence number of the heartbeat.
+    pub sequence: u64,
+
+    /// Targets the heartbeat couldn't be sent to, and why.
+    pub failures: Vec<(SocketAddr, io::Error)>,
+}
+
+impl fmt::Display for SendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Failed to send heartbeat {} to {} target(s)",
+            self.sequence,
+            self.failures.len()
+        )
+    }
+}
+
+impl std::error::Error for SendError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.failures
+            .first()
+            .map(|(_, err)| err as &(dyn std::error::Error + 'static))
+    }
+}
+
+/// Periodically sends heartbeats to a set of targets over UDP.
+pub struct HeartbeatSender {
+    socket: UdpSocket,
+    node_id: u64,
+    incarnation: u64,
+    targets: Vec<SocketAddr>,
+    interval: Duration,
+    jitter: Duration,
+    scheduler: HeartbeatScheduler,
+    sequence: u64,
+}
+
+impl HeartbeatSender {
+    /// Binds the sender socket to `addr`.
+    pub fn bind(addr: impl ToSocketAddrs, node_id: u64) -> io::Result<Self> {
+        Ok(Self {
+            socket: UdpSocket::bind(addr)?,
+            node_id,
+            incarnation: unix_timestamp_ms(),
+            targets: Vec::new(),
+            interval: Duration::from_secs(1),
+            jitter: Duration::ZERO,
+            scheduler: HeartbeatScheduler::new(Duration::from_secs(1)),
+            sequence: 0,
+        })
+    }
+
+    /// Incarnation of the node, which must change when the node restarts so
+    /// that receivers can tell new heartbeats from stale ones.
+    ///
+    /// Default: time the sender was created at, in milliseconds since UNIX
+    /// epoch
+    pub fn incarnation(mut self, incarnation: u64) -> Self {
+        self.incarnation = incarnation;
+        self
+    }
+
+    /// Adds a target to send heartbeats to.
+    pub fn target(mut self, addr: SocketAddr) -> Self {
+        self.targets.push(addr);
+        self
+    }
+
+    /// Interval between heartbeats.
+    ///
+    /// Default: 1s
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self.scheduler = HeartbeatScheduler::new(interval).jitter(self.jitter);
+        self
+    }
+
+    /// Maximum random deviation from the interval, to avoid synchronized
+    /// bursts of heartbeats from many senders.
+    ///
+    /// Default: 0
+    pub fn jitter(mut self, jitter: Duration) -> Self {
+        self.jitter = jitter;
+        self.scheduler = HeartbeatScheduler::new(self.interval).jitter(jitter);
+        self
+    }
+
+    /// Sends a heartbeat to all targets immediately, returning its sequence
+    /// number.
+    pub fn send(&mut self) -> io::Result<u64> {
+        self.send_with_payload(&[])
+    }
+
+    /// Sends a heartbeat with a piggybacked payload to all targets immediately,
+    /// returning its sequence number.
+    ///
+    /// The heartbeat is sent to every target even if sending to some of them
+    /// fails, in which case the error wraps a [`SendError`]. Frames longer
+    /// than [`MAX_DATAGRAM_LEN`] are rejected with
+    /// [`io::ErrorKind::InvalidInput`] without sending anything.
+    pub fn send_with_payload(&mut self, payload: &[u8]) -> io::Result<u64> {
+        let frame = Frame {
+            node_id: self.node_id,
+            incarnation: self.incarnation,
+            sequence: self.sequence,
+            timestamp: unix_timestamp_ms(),
+            payload,
+        };
+
+        if frame.encoded_len() > MAX_DATAGRAM_LEN {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Frame doesn't fit into a datagram: {} bytes",
+                    frame.encoded_len()
+                ),
+            ));
+        }
+
+        let buf = frame
+            .to_vec()
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+
+        self.sequence += 1;
+
+        let failures: Vec<_> = self
+            .targets
+            .iter()
+            .filter_map(|target| {
+                let err = self.socket.send_to(&buf, target).err()?;
+                Some((*target, err))
+            })
+            .collect();
+
+        match failures.first() {
+            None => Ok(frame.sequence),
+            Some((_, err)) => Err(io::Error::new(err.kind(), SendError {
+                sequence: frame.sequence,
+                failures,
+            })),
+        }
+    }
+
+    /// Returns the delay until the next heartbeat, i.e. interval with random
+    /// jitter applied.
+    pub fn next_delay(&mut self) -> Duration {
+        self.scheduler.next_interval()
+    }
+
+    /// Sends heartbeats on a background thread until the returned [`Task`] is
+    /// stopped or dropped. Send errors are ignored.
+    pub fn spawn(mut self) -> Task {
+        Task::spawn(move |stop| {
+            while let Some(deadline) = self.scheduler.next() {
+                park_until(Instant::now() + self.scheduler.delay_until(deadline), stop);
+
+                if stop.load(Ordering::Relaxed) {
+                    break;
+                }
+
+                let _ = self.send();
+                self.scheduler.record_sent();
+            }
+        })
+    }
+}
+
+/// Receives heartbeats over UDP and records them into a [`Registry`] keyed by
+/// sender node ID.
+///
+/// Duplicated and reordered heartbeats, i.e. ones that are not newer than the
+/// last heartbeat received from the same node, are ignored. Nodes removed from
+/// the registry are forgotten, so that a heartbeat from a removed node is
+/// accepted whatever its sequence number.
+pub struct HeartbeatReceiver<D> {
+    socket: UdpSocket,
+    registry: Arc<Registry<u64, D>>,
+    latest: Mutex<HashMap<u64, (u64, u64)>>,
+}
+
+impl<D: Detector> HeartbeatReceiver<D> {
+    /// Binds the receiver socket to `addr`.
+    pub fn bind(addr: impl ToSocketAddrs, registry: Arc<Registry<u64, D>>) -> io::Result<Self> {
+        Ok(Self {
+            socket: UdpSocket::bind(addr)?,
+            registry,
+            latest: Default::default(),
+        })
+    }
+
+    /// Address the receiver is bound to.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+
+    /// Blocks until a valid heartbeat is received into `buf`, and records it
+    /// into the registry. Malformed and stale datagrams are ignored.
+    ///
+    /// Datagrams that don't fit into `buf` are truncated and therefore
+    /// ignored, so it should be large enough for the expected payloads, up to
+    /// [`MAX_DATAGRAM_LEN`].
+    pub fn recv<'a>(&self, buf: &'a mut [u8]) -> io::Result<(Frame<'a>, SocketAddr)> {
+        let (header, len, addr) = loop {
+            let (len, addr) = self.socket.recv_from(buf)?;
+
+            if let Ok(frame) = Frame::decode(&buf[..len]) {
+                if self.is_newer(&frame) {
+                    // Detach the header from `buf`, which is received into
+                    // again if the frame is rejected.
+                    let header = (
+                        frame.node_id,
+                        frame.incarnation,
+                        frame.sequence,
+                        frame.timestamp,
+                    );
+
+                    break (header, len, addr);
+                }
+            }
+        };
+
+        let (node_id, incarnation, sequence, timestamp) = header;
+        let frame = Frame {
+            node_id,
+            incarnation,
+            sequence,
+            timestamp,
+            payload: &buf[HEADER_LEN..len],
+        };
+
+        self.registry.heartbeat(&node_id);
+
+        Ok((frame, addr))
+    }
+
+    fn is_newer(&self, frame: &Frame) -> bool {
+        let mut latest = self.latest.lock().unwrap();
+
+        // Every accepted node is monitored, so the map only outgrows the
+        // registry after removals.
+        if latest.len() > self.registry.len() {
+            latest.retain(|node_id, _| self.registry.is_monitoring(node_id));
+        }
+
+        let version = (frame.incarnation, frame.sequence);
+
+        match latest.get(&frame.node_id) {
+            Some(last) if *last >= version => false,
+            _ => {
+                latest.insert(frame.node_id, version);
+                true
+            }
+        }
+    }
+
+    /// Receives heartbeats on a background thread until the returned [`Task`]
+    /// is stopped or dropped.
+    pub fn spawn(self) -> io::Result<Task>
+    where
+        D: Send + Sync + 'static,
+    {
+        // Wake up periodically to check whether the task has been stopped.
+        self.socket
+            .set_read_timeout(Some(Duration::from_millis(100)))?;
+
+        Ok(Task::spawn(move |stop| {
+            let mut buf = vec![0; MAX_DATAGRAM_LEN];
+
+            while !stop.load(Ordering::Relaxed) {
+                let _ = self.recv(&mut buf);
+            }
+        }))
+    }
+}
+
+/// Handle to a background sender or receiver thread. The thread is stopped
+/// when the handle is dropped.
+pub struct Task {
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Task {
+    fn spawn(f: impl FnOnce(&AtomicBool) + Send + 'static) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = thread::spawn({
+            let stop = stop.clone();
+            move || f(&stop)
+        });
+
+        Self {
+            stop,
+            thread: Some(thread),
+        }
+    }
+
+    /// Stops the thread and waits for it to finish.
+    pub fn stop(self) {
+        drop(self);
+    }
+}
+
+impl Drop for Task {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+
+        if let Some(thread) = self.thread.take() {
+            thread.thread().unpark();
+            let _ = thread.join();
+        }
+    }
+}
+
+fn park_until(deadline: Instant, stop: &AtomicBool) {
+    loop {
+        let now = Instant::now();
+
+        if now >= deadline || stop.load(Ordering::Relaxed) {
+            return;
+        }
+
+        thread::park_timeout(deadline - now);
+    }
+}
+
+fn unix_timestamp_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
@@ -0,0 +1,267 @@
+//! Rendering of detector state in the Prometheus text exposition format.
+
+#[cfg(feature = "http")]
+pub use server::MetricsServer;
+use {
+    crate::{Detector, DetectorStats, Status},
+    std::fmt::{self, Display, Write as _},
+};
+
+struct Metric {
+    name: &'static str,
+    kind: &'static str,
+    help: &'static str,
+    value: fn(&DetectorStats) -> Option<f64>,
+}
+
+const METRICS: &[Metric] = &[
+    Metric {
+        name: "phi_accrual_phi",
+        kind: "gauge",
+        help: "Suspicion level of the monitored resource.",
+        value: |stats| Some(stats.phi),
+    },
+    Metric {
+        name: "phi_accrual_available",
+        kind: "gauge",
+        help: "Whether the monitored resource is considered available.",
+        value: |stats| {
+            Some(if stats.status == Status::Unavailable {
+                0.
+            } else {
+                1.
+            })
+        },
+    },
+    Metric {
+        name: "phi_accrual_interval_mean_seconds",
+        kind: "gauge",
+        help: "Mean heartbeat inter-arrival time.",
+        value: |stats| Some(stats.mean.as_secs_f64()),
+    },
+    Metric {
+        name: "phi_accrual_interval_std_deviation_seconds",
+        kind: "gauge",
+        help: "Standard deviation of heartbeat inter-arrival times.",
+        value: |stats| Some(stats.std_deviation.as_secs_f64()),
+    },
+    Metric {
+        name: "phi_accrual_heartbeats_total",
+        kind: "counter",
+        help: "Total number of heartbeats received.",
+        value: |stats| Some(stats.heartbeat_count as f64),
+    },
+    Metric {
+        name: "phi_accrual_time_since_last_heartbeat_seconds",
+        kind: "gauge",
+        help: "Time elapsed since the most recent heartbeat.",
+        value: |stats| {
+            stats
+                .time_since_last_heartbeat
+                .map(|time| time.as_secs_f64())
+        },
+    },
+];
+
+/// Renders the state of the `detectors`, labelled by `peer=<key>`, in the
+/// Prometheus text exposition format.
+///
+/// ```
+/// use phi_accrual_failure_detector::{prometheus, Registry, SyncDetector};
+///
+/// let registry = Registry::new(|_: &String| SyncDetector::default());
+/// registry.heartbeat(&"10.0.0.1:7000".to_owned());
+///
+/// let output = prometheus::render(registry.detectors());
+/// assert!(output.contains(r#"phi_accrual_heartbeats_total{peer="10.0.0.1:7000"} 1"#));
+/// ```
+pub fn render<K, D>(detectors: impl IntoIterator<Item = (K, D)>) -> String
+where
+    K: Display,
+    D: Detector,
+{
+    let stats: Vec<_> = detectors
+        .into_iter()
+        .map(|(key, detector)| (escape(&key.to_string()), detector.stats()))
+        .collect();
+
+    let mut output = String::new();
+
+    for metric in METRICS {
+        let _ = writeln!(output, "# HELP {} {}", metric.name, metric.help);
+        let _ = writeln!(output, "# TYPE {} {}", metric.name, metric.kind);
+
+        for (peer, stats) in &stats {
+            if let Some(value) = (metric.value)(stats) {
+                let value = Value(value);
+                let _ = writeln!(output, "{}{{peer=\"{peer}\"}} {value}", metric.name);
+            }
+        }
+    }
+
+    output
+}
+
+/// Sample value, with non-finite values spelled as the exposition format
+/// expects, e.g. phi of a resource silent for long enough is `+Inf`.
+struct Value(f64);
+
+impl Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            value if value.is_nan() => f.write_str("NaN"),
+            f64::INFINITY => f.write_str("+Inf"),
+            f64::NEG_INFINITY => f.write_str("-Inf"),
+            value => value.fmt(f),
+        }
+    }
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', r"\\")
+        .replace('"', r#"\""#)
+        .replace('\n', r"\n")
+}
+
+#[cfg(feature = "http")]
+mod server {
+    use std::{
+        io::{self, BufRead, BufReader, Read, Write},
+        net::{TcpListener, TcpStream, ToSocketAddrs},
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+        thread::{self, JoinHandle},
+        time::Duration,
+    };
+
+    /// Time a connection may take to send the request or receive the response.
+    const TIMEOUT: Duration = Duration::from_secs(1);
+
+    /// Maximum length of a request, including headers.
+    const MAX_REQUEST_LEN: u64 = 8 * 1024;
+
+    /// Maximum number of request header lines.
+    const MAX_HEADERS: usize = 100;
+
+    /// Minimal HTTP server exposing metrics in the Prometheus text format on
+    /// `/metrics`.
+    ///
+    /// Connections are handled sequentially on a background thread, which is
+    /// stopped when the server is dropped. Each connection has 1s to send its
+    /// request, so an idle client delays other scrapes and shutdown by at most
+    /// that long.
+    pub struct MetricsServer {
+        local_addr: std::net::SocketAddr,
+        shutdown: Arc<AtomicBool>,
+        thread: Option<JoinHandle<()>>,
+    }
+
+    impl MetricsServer {
+        /// Binds the server to `addr`, serving the output of `render` on each
+        /// request, e.g. [`render`](super::render) of a
+        /// [`Registry`](crate::Registry).
+        pub fn bind(
+            addr: impl ToSocketAddrs,
+            render: impl Fn() -> String + Send + 'static,
+        ) -> io::Result<Self> {
+            let listener = TcpListener::bind(addr)?;
+            let local_addr = listener.local_addr()?;
+            let shutdown = Arc::new(AtomicBool::new(false));
+
+            let thread = thread::spawn({
+                let shutdown = shutdown.clone();
+
+                move || {
+                    for stream in listener.incoming() {
+                        if shutdown.load(Ordering::Relaxed) {
+                            break;
+                        }
+
+                        if let Ok(stream) = stream {
+                            let _ = handle(stream, &render);
+                        }
+                    }
+                }
+            });
+
+            Ok(Self {
+                local_addr,
+                shutdown,
+                thread: Some(thread),
+            })
+        }
+
+        /// Address the server is bound to.
+        pub fn local_addr(&self) -> std::net::SocketAddr {
+            self.local_addr
+        }
+    }
+
+    impl Drop for MetricsServer {
+        fn drop(&mut self) {
+            self.shutdown.store(true, Ordering::Relaxed);
+
+            // Unblock the listener.
+            let _ = TcpStream::connect(self.local_addr);
+
+            if let Some(thread) = self.thread.take() {
+                let _ = thread.join();
+            }
+        }
+    }
+
+    fn handle(mut stream: TcpStream, render: &impl Fn() -> String) -> io::Result<()> {
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        stream.set_write_timeout(Some(TIMEOUT))?;
+
+        let mut reader = BufReader::new((&stream).take(MAX_REQUEST_LEN));
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line)?;
+
+        // Drain the request headers.
+        let mut line = String::new();
+        let mut headers = 0;
+
+        while reader.read_line(&mut line)? > 2 {
+            headers += 1;
+
+            if headers > MAX_HEADERS {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "too many request headers",
+                ));
+            }
+
+            line.clear();
+        }
+
+        let mut parts = request_line.split_whitespace();
+        let (status, body) = match (parts.next(), parts.next()) {
+            (Some("GET"), Some("/metrics")) => ("200 OK", render()),
+            _ => ("404 Not Found", String::new()),
+        };
+
+        write!(
+            stream,
+            "HTTP/1.1 {status}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: \
+             {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        )?;
+
+        stream.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn label_escaping() {
+        assert_eq!(escape(r#"a"b\c"#), r#"a\"b\\c"#);
+        assert_eq!(escape("a\nb"), r"a\nb");
+    }
+}
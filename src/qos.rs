@@ -0,0 +1,283 @@
+//! Quality of service metrics of failure detectors, as defined by Chen, Toueg
+//! and Aguilera in "On the Quality of Service of Failure Detectors".
+//!
+//! A [`Trace`] records the ground truth, i.e. when the monitored process
+//! crashed and recovered, along with the verdicts of a detector over time.
+//! [`Trace::qos`] then computes:
+//!
+//! - Detection time (T_D): time from a crash to the moment the detector starts
+//!   suspecting the process permanently, i.e. until it recovers.
+//! - Mistake recurrence time (T_MR): time between the starts of consecutive
+//!   mistakes.
+//! - Mistake duration (T_M): time it takes the detector to correct a mistake.
+//! - Good period duration (T_G): time from the end of a mistake to the start of
+//!   the next one.
+//! - Query accuracy probability (P_A): probability that the verdict is correct
+//!   at a random time the process is up.
+//!
+//! A mistake is a period the detector suspects the process while it's up.
+//! Recurrence times and good periods are only measured between mistakes within
+//! the same up period.
+
+use {crate::Detector, std::time::Duration};
+
+/// Ground truth and detector verdicts over time, relative to the start of the
+/// trace.
+///
+/// The process is initially up and trusted by the detector.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trace {
+    /// Transitions of the process state, `true` when up.
+    truth: Vec<(Duration, bool)>,
+
+    /// Transitions of the detector verdict, `true` when available.
+    verdicts: Vec<(Duration, bool)>,
+}
+
+impl Default for Trace {
+    fn default() -> Self {
+        Self {
+            truth: vec![(Duration::ZERO, true)],
+            verdicts: vec![(Duration::ZERO, true)],
+        }
+    }
+}
+
+impl Trace {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records that the process crashed at `at`.
+    ///
+    /// Panics if `at` is before the last recorded crash or recovery.
+    pub fn crash(&mut self, at: Duration) {
+        Self::push(&mut self.truth, at, false);
+    }
+
+    /// Records that the process recovered at `at`.
+    ///
+    /// Panics if `at` is before the last recorded crash or recovery.
+    pub fn recover(&mut self, at: Duration) {
+        Self::push(&mut self.truth, at, true);
+    }
+
+    /// Records the verdict of the detector at `at`, `true` if the process is
+    /// considered available.
+    ///
+    /// Panics if `at` is before the last recorded verdict.
+    pub fn record(&mut self, at: Duration, available: bool) {
+        Self::push(&mut self.verdicts, at, available);
+    }
+
+    /// Records the current verdict of `detector` at `at`.
+    ///
+    /// Panics if `at` is before the last recorded verdict.
+    pub fn observe(&mut self, at: Duration, detector: &impl Detector) {
+        self.record(at, detector.is_available());
+    }
+
+    fn push(transitions: &mut Vec<(Duration, bool)>, at: Duration, state: bool) {
+        // Safe unwrap, there's always the initial state.
+        let (last, last_state) = *transitions.last().unwrap();
+        assert!(at >= last, "trace must be recorded in chronological order");
+
+        if state != last_state {
+            transitions.push((at, state));
+        }
+    }
+
+    /// Computes the QoS metrics of the trace up to `end`.
+    pub fn qos(&self, end: Duration) -> Qos {
+        let mut times: Vec<_> = self
+            .truth
+            .iter()
+            .chain(&self.verdicts)
+            .map(|(time, _)| *time)
+            .filter(|time| *time < end)
+            .collect();
+
+        times.sort();
+        times.dedup();
+
+        let mut qos = Qos::default();
+
+        // Start of the current down period, suspicion and mistake.
+        let mut crashed = None;
+        let mut suspected = None;
+        let mut mistake = None;
+
+        // Start of the last mistake and end of the last corrected mistake
+        // within the current up period.
+        let mut last_mistake = None;
+        let mut last_correction = None;
+
+        for (i, start) in times.iter().copied().enumerate() {
+            let stop = times.get(i + 1).copied().unwrap_or(end);
+            let up = state_at(&self.truth, start);
+            let available = state_at(&self.verdicts, start);
+            let mistaken = up && !available;
+
+            if up {
+                if let Some(crash) = crashed.take() {
+                    qos.detect(crash, suspected);
+                }
+
+                qos.up_time += stop - start;
+            } else if crashed.is_none() {
+                crashed = Some(start);
+                last_mistake = None;
+                last_correction = None;
+            }
+
+            if available {
+                suspected = None;
+            } else {
+                suspected.get_or_insert(start);
+            }
+
+            match mistake {
+                None if mistaken => {
+                    if let Some(last) = last_mistake.replace(start) {
+                        qos.mistake_recurrence_times.push(start - last);
+                    }
+
+                    if let Some(correction) = last_correction.take() {
+                        qos.good_periods.push(start - correction);
+                    }
+
+                    mistake = Some(start);
+                }
+
+                Some(mistake_start) if !mistaken => {
+                    qos.mistakes.push(Mistake {
+                        start: mistake_start,
+                        end: start,
+                    });
+
+                    if up {
+                        last_correction = Some(start);
+                    }
+
+                    mistake = None;
+                }
+
+                _ => {}
+            }
+        }
+
+        if let Some(crash) = crashed {
+            qos.detect(crash, suspected);
+        }
+
+        if let Some(start) = mistake {
+            qos.mistakes.push(Mistake { start, end });
+        }
+
+        qos
+    }
+}
+
+fn state_at(transitions: &[(Duration, bool)], time: Duration) -> bool {
+    let i = transitions.partition_point(|(at, _)| *at <= time);
+    // The initial state is at zero, so `i` is at least 1.
+    transitions[i - 1].1
+}
+
+/// Period the detector was suspecting the process while it was up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mistake {
+    pub start: Duration,
+
+    /// End of the mistake, i.e. when the detector trusted the process again,
+    /// the process crashed, or the trace ended.
+    pub end: Duration,
+}
+
+impl Mistake {
+    pub fn duration(&self) -> Duration {
+        self.end - self.start
+    }
+}
+
+/// QoS metrics of a [`Trace`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Qos {
+    /// Detection times of the crashes detected before the process recovered or
+    /// the trace ended.
+    pub detection_times: Vec<Duration>,
+
+    /// Number of crashes not detected before the process recovered or the trace
+    /// ended.
+    pub undetected: usize,
+
+    pub mistakes: Vec<Mistake>,
+    pub mistake_recurrence_times: Vec<Duration>,
+    pub good_periods: Vec<Duration>,
+
+    /// Total time the process was up.
+    pub up_time: Duration,
+}
+
+impl Qos {
+    fn detect(&mut self, crash: Duration, suspected: Option<Duration>) {
+        match suspected {
+            Some(suspected) => self.detection_times.push(suspected.saturating_sub(crash)),
+            None => self.undetected += 1,
+        }
+    }
+
+    /// Mean detection time (E(T_D)).
+    pub fn mean_detection_time(&self) -> Option<Duration> {
+        mean(&self.detection_times)
+    }
+
+    /// Maximum detection time (T_D upper bound).
+    pub fn max_detection_time(&self) -> Option<Duration> {
+        self.detection_times.iter().max().copied()
+    }
+
+    /// Mean mistake recurrence time (E(T_MR)).
+    pub fn mean_mistake_recurrence_time(&self) -> Option<Duration> {
+        mean(&self.mistake_recurrence_times)
+    }
+
+    /// Mean mistake duration (E(T_M)).
+    pub fn mean_mistake_duration(&self) -> Option<Duration> {
+        let durations: Vec<_> = self.mistakes.iter().map(Mistake::duration).collect();
+        mean(&durations)
+    }
+
+    /// Mean good period duration (E(T_G)).
+    pub fn mean_good_period(&self) -> Option<Duration> {
+        mean(&self.good_periods)
+    }
+
+    /// Number of mistakes per hour the process was up (λ_M).
+    pub fn mistake_rate(&self) -> f64 {
+        if self.up_time.is_zero() {
+            0.
+        } else {
+            self.mistakes.len() as f64 / (self.up_time.as_secs_f64() / 3600.)
+        }
+    }
+
+    /// Query accuracy probability (P_A), i.e. the fraction of the up time the
+    /// detector trusted the process. `1.0` if the process was never up.
+    pub fn query_accuracy(&self) -> f64 {
+        if self.up_time.is_zero() {
+            return 1.;
+        }
+
+        let mistaken: Duration = self.mistakes.iter().map(Mistake::duration).sum();
+        1. - mistaken.as_secs_f64() / self.up_time.as_secs_f64()
+    }
+}
+
+fn mean(durations: &[Duration]) -> Option<Duration> {
+    if durations.is_empty() {
+        None
+    } else {
+        Some(durations.iter().sum::<Duration>() / durations.len() as u32)
+    }
+}
@@ -0,0 +1,313 @@
+//! Quorum-based confirmation of suspicions.
+//!
+//! A single observer may suspect a healthy resource because of a bad link
+//! between the two. When the local detector of a subject becomes unavailable,
+//! [`Confirmer`] asks `k` other observers for their phi of the same subject,
+//! and only declares the subject dead once a quorum of observers, including
+//! the local one, agrees.
+//!
+//! The protocol is driven by the caller: [`Confirmer::tick`] starts new rounds
+//! and times out pending ones, and incoming messages are passed to
+//! [`Confirmer::handle`].
+
+use {
+    crate::{rng::Rng, transport::Transport, Detector, Registry},
+    std::{
+        collections::{HashMap, HashSet},
+        hash::Hash,
+        sync::Arc,
+    },
+};
+
+/// Confirmation protocol message.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Message<I> {
+    /// Request for the phi of the subject.
+    PhiRequest { round: u64, subject: I },
+
+    /// Phi of the subject according to the sender, or `None` if the sender
+    /// doesn't monitor the subject.
+    PhiResponse {
+        round: u64,
+        subject: I,
+        phi: Option<f64>,
+        available: bool,
+    },
+}
+
+/// Outcome of a confirmation round.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict<I> {
+    /// A quorum of observers agrees the subject is unavailable.
+    Confirmed { subject: I, votes: usize },
+
+    /// The quorum can't be reached, either because enough observers
+    /// disagree, or because the round has timed out.
+    Rejected { subject: I, votes: usize },
+}
+
+struct Round<I> {
+    subject: I,
+    started_at: u64,
+    required: usize,
+    pending: HashSet<I>,
+    votes: usize,
+}
+
+/// Local observer taking part in confirmation rounds.
+pub struct Confirmer<I, D, T> {
+    id: I,
+    transport: T,
+    registry: Arc<Registry<I, D>>,
+    observers: Vec<I>,
+    rounds: HashMap<u64, Round<I>>,
+    // Subjects with a confirmed verdict, until they become available again.
+    confirmed: HashSet<I>,
+    // Subjects with a rejected verdict, and the tick it was reached at.
+    rejected: HashMap<I, u64>,
+    rng: Rng,
+    now: u64,
+    next_round: u64,
+    k: usize,
+    quorum: Option<usize>,
+    timeout: u64,
+    retry_interval: u64,
+}
+
+impl<I, D, T> Confirmer<I, D, T>
+where
+    I: Eq + Hash + Clone,
+    D: Detector,
+    T: Transport<I, Message<I>>,
+{
+    /// Creates an observer confirming the suspicions of the detectors in the
+    /// registry with the other `observers`.
+    pub fn new(id: I, transport: T, registry: Arc<Registry<I, D>>, observers: Vec<I>) -> Self {
+        let observers = observers
+            .into_iter()
+            .filter(|observer| *observer != id)
+            .collect();
+
+        Self {
+            id,
+            transport,
+            registry,
+            observers,
+            rounds: HashMap::new(),
+            confirmed: HashSet::new(),
+            rejected: HashMap::new(),
+            rng: Rng::from_entropy(),
+            now: 0,
+            next_round: 0,
+            k: 3,
+            quorum: None,
+            timeout: 5,
+            retry_interval: 10,
+        }
+    }
+
+    /// Number of observers asked to confirm a suspicion. Defaults to 3.
+    pub fn k(mut self, k: usize) -> Self {
+        self.k = k;
+        self
+    }
+
+    /// Number of observers, including the local one, that must agree on a
+    /// suspicion to confirm it. Defaults to a majority of the observers taking
+    /// part in the round.
+    pub fn quorum(mut self, quorum: usize) -> Self {
+        self.quorum = Some(quorum.max(1));
+        self
+    }
+
+    /// Number of ticks to wait for responses before rejecting the suspicion.
+    /// Defaults to 5.
+    pub fn timeout(mut self, ticks: u64) -> Self {
+        self.timeout = ticks;
+        self
+    }
+
+    /// Number of ticks after a rejected suspicion before it's confirmed again.
+    /// Defaults to 10.
+    pub fn retry_interval(mut self, ticks: u64) -> Self {
+        self.retry_interval = ticks;
+        self
+    }
+
+    /// Seed of the random observer selection. Defaults to a random seed.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.rng = Rng::new(seed);
+        self
+    }
+
+    /// Returns the identifier of the local observer.
+    pub fn id(&self) -> &I {
+        &self.id
+    }
+
+    /// Returns the registry of the local observer.
+    pub fn registry(&self) -> &Arc<Registry<I, D>> {
+        &self.registry
+    }
+
+    /// Returns `true` if the subject has been confirmed dead, and hasn't become
+    /// available since.
+    pub fn is_confirmed(&self, subject: &I) -> bool {
+        self.confirmed.contains(subject)
+    }
+
+    /// Starts confirmation rounds for the subjects suspected by the local
+    /// detectors, and returns the verdicts of the rounds that have timed out.
+    pub fn tick(&mut self) -> Vec<Verdict<I>> {
+        self.now += 1;
+
+        let now = self.now;
+        let timeout = self.timeout;
+        let mut verdicts = Vec::new();
+
+        let expired: Vec<_> = self
+            .rounds
+            .iter()
+            .filter(|(_, round)| now - round.started_at >= timeout)
+            .map(|(id, _)| *id)
+            .collect();
+
+        for id in expired {
+            // Safe unwrap, the round exists.
+            let round = self.rounds.remove(&id).unwrap();
+            verdicts.push(self.reject(round));
+        }
+
+        for (subject, detector) in self.registry.detectors() {
+            if detector.is_available() {
+                self.confirmed.remove(&subject);
+                self.rejected.remove(&subject);
+                continue;
+            }
+
+            let retry = self
+                .rejected
+                .get(&subject)
+                .is_none_or(|rejected_at| now - rejected_at >= self.retry_interval);
+
+            let pending = self.rounds.values().any(|round| round.subject == subject);
+
+            if retry && !pending && !self.confirmed.contains(&subject) {
+                verdicts.extend(self.start_round(subject));
+            }
+        }
+
+        verdicts
+    }
+
+    /// Handles a message received from another observer, returning the verdict
+    /// if the message has completed a round.
+    pub fn handle(&mut self, from: I, message: Message<I>) -> Option<Verdict<I>> {
+        match message {
+            Message::PhiRequest { round, subject } => {
+                let (phi, available) = match self.registry.get(&subject) {
+                    Some(detector) => (Some(detector.phi()), detector.is_available()),
+                    None => (None, true),
+                };
+
+                self.transport.send(&from, Message::PhiResponse {
+                    round,
+                    subject,
+                    phi,
+                    available,
+                });
+
+                None
+            }
+
+            Message::PhiResponse {
+                round: id,
+                subject,
+                phi,
+                available,
+            } => {
+                let round = self.rounds.get_mut(&id)?;
+
+                if round.subject != subject || !round.pending.remove(&from) {
+                    return None;
+                }
+
+                if phi.is_some() && !available {
+                    round.votes += 1;
+                }
+
+                let possible = round.votes + round.pending.len();
+
+                if round.votes >= round.required {
+                    // Safe unwrap, the round exists.
+                    let round = self.rounds.remove(&id).unwrap();
+                    self.confirmed.insert(round.subject.clone());
+
+                    Some(Verdict::Confirmed {
+                        subject: round.subject,
+                        votes: round.votes,
+                    })
+                } else if possible < round.required {
+                    // Safe unwrap, the round exists.
+                    let round = self.rounds.remove(&id).unwrap();
+                    Some(self.reject(round))
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    fn start_round(&mut self, subject: I) -> Option<Verdict<I>> {
+        let mut observers: Vec<_> = self
+            .observers
+            .iter()
+            .filter(|observer| **observer != subject)
+            .cloned()
+            .collect();
+
+        self.rng.shuffle(&mut observers);
+        observers.truncate(self.k);
+
+        // Majority of the observers taking part, including the local one.
+        let participants = observers.len() + 1;
+
+        self.next_round += 1;
+        let round = Round {
+            subject: subject.clone(),
+            started_at: self.now,
+            required: self.quorum.unwrap_or(participants / 2 + 1),
+            pending: observers.iter().cloned().collect(),
+            // The local observer agrees with its own suspicion.
+            votes: 1,
+        };
+
+        // Without enough observers, the local verdict may be sufficient.
+        if round.votes >= round.required {
+            self.confirmed.insert(subject.clone());
+            return Some(Verdict::Confirmed {
+                subject,
+                votes: round.votes,
+            });
+        }
+
+        for observer in &observers {
+            self.transport.send(observer, Message::PhiRequest {
+                round: self.next_round,
+                subject: subject.clone(),
+            });
+        }
+
+        self.rounds.insert(self.next_round, round);
+        None
+    }
+
+    fn reject(&mut self, round: Round<I>) -> Verdict<I> {
+        self.rejected.insert(round.subject.clone(), self.now);
+
+        Verdict::Rejected {
+            subject: round.subject,
+            votes: round.votes,
+        }
+    }
+}
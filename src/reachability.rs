@@ -0,0 +1,307 @@
+use {
+    crate::{Detector, Registry},
+    std::{collections::HashMap, hash::Hash},
+};
+
+/// Reachability of a subject according to an observer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReachabilityStatus {
+    Reachable,
+    Unreachable,
+
+    /// The subject has left or has been removed. Final, once recorded it's
+    /// never changed.
+    Terminated,
+}
+
+/// Reachability of a subject according to an observer, versioned by the
+/// observer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReachabilityRecord<I> {
+    pub observer: I,
+    pub subject: I,
+    pub status: ReachabilityStatus,
+    pub version: u64,
+}
+
+/// Table of the verdicts of multiple observers about the reachability of
+/// subjects, e.g. cluster members.
+///
+/// Based on the reachability table of Pekko cluster. Each observer only
+/// changes its own records, incrementing its version with every change, which
+/// makes tables of different nodes mergeable by picking the most recent version
+/// of each observer's records.
+///
+/// Records of observers that consider all their subjects reachable are pruned,
+/// so subjects without records are reachable.
+#[derive(Debug, Clone)]
+pub struct Reachability<I> {
+    observers: HashMap<I, Observer<I>>,
+}
+
+#[derive(Debug, Clone)]
+struct Observer<I> {
+    version: u64,
+    records: HashMap<I, (ReachabilityStatus, u64)>,
+}
+
+impl<I> Reachability<I>
+where
+    I: Eq + Hash + Clone,
+{
+    pub fn new() -> Self {
+        Self {
+            observers: HashMap::new(),
+        }
+    }
+
+    /// Records that the observer can reach the subject.
+    pub fn reachable(&mut self, observer: &I, subject: &I) {
+        self.change(observer, subject, ReachabilityStatus::Reachable);
+    }
+
+    /// Records that the observer can't reach the subject.
+    pub fn unreachable(&mut self, observer: &I, subject: &I) {
+        self.change(observer, subject, ReachabilityStatus::Unreachable);
+    }
+
+    /// Records that the subject has left or has been removed.
+    pub fn terminated(&mut self, observer: &I, subject: &I) {
+        self.change(observer, subject, ReachabilityStatus::Terminated);
+    }
+
+    /// Records the verdicts of all detectors of the `observer`'s registry.
+    /// Unavailable resources are recorded as unreachable, the rest as
+    /// reachable.
+    pub fn update<D: Detector>(&mut self, observer: &I, registry: &Registry<I, D>) {
+        for (subject, detector) in registry.detectors() {
+            if detector.is_available() {
+                self.reachable(observer, &subject);
+            } else {
+                self.unreachable(observer, &subject);
+            }
+        }
+    }
+
+    /// Merges the records of another table, keeping the most recent records of
+    /// each observer.
+    pub fn merge(&mut self, other: &Self) {
+        for (id, observer) in &other.observers {
+            let newer = self
+                .observers
+                .get(id)
+                .is_none_or(|known| observer.version > known.version);
+
+            if newer {
+                self.observers.insert(id.clone(), observer.clone());
+            }
+        }
+    }
+
+    /// Returns the aggregated status of the subject: terminated if any
+    /// observer has recorded it as terminated, unreachable if any observer has
+    /// recorded it as unreachable, and reachable otherwise.
+    pub fn status(&self, subject: &I) -> ReachabilityStatus {
+        let mut status = ReachabilityStatus::Reachable;
+
+        for observer in self.observers.values() {
+            match observer.records.get(subject) {
+                Some((ReachabilityStatus::Terminated, _)) => {
+                    return ReachabilityStatus::Terminated;
+                }
+
+                Some((ReachabilityStatus::Unreachable, _)) => {
+                    status = ReachabilityStatus::Unreachable;
+                }
+
+                _ => {}
+            }
+        }
+
+        status
+    }
+
+    /// Returns the status of the subject recorded by the observer.
+    pub fn status_by(&self, observer: &I, subject: &I) -> ReachabilityStatus {
+        self.observers
+            .get(observer)
+            .and_then(|observer| observer.records.get(subject))
+            .map_or(ReachabilityStatus::Reachable, |(status, _)| *status)
+    }
+
+    /// Returns the observers that can't reach the subject.
+    pub fn observers_unreachable(&self, subject: &I) -> Vec<I> {
+        self.observers
+            .iter()
+            .filter(|(_, observer)| {
+                observer
+                    .records
+                    .get(subject)
+                    .is_some_and(|(status, _)| *status == ReachabilityStatus::Unreachable)
+            })
+            .map(|(id, _)| id.clone())
+            .collect()
+    }
+
+    /// Returns `true` if at least one observer can't reach the subject.
+    pub fn is_unreachable_by_any(&self, subject: &I) -> bool {
+        !self.observers_unreachable(subject).is_empty()
+    }
+
+    /// Returns `true` if a strict majority of the `observers` monitoring the
+    /// subject can't reach it.
+    pub fn is_unreachable_by_majority(&self, subject: &I, observers: usize) -> bool {
+        self.observers_unreachable(subject).len() > observers / 2
+    }
+
+    /// Returns the version of the observer's records, `0` if it hasn't recorded
+    /// anything.
+    pub fn version(&self, observer: &I) -> u64 {
+        self.observers
+            .get(observer)
+            .map_or(0, |observer| observer.version)
+    }
+
+    /// Returns all records, in no particular order.
+    pub fn records(&self) -> Vec<ReachabilityRecord<I>> {
+        self.observers
+            .iter()
+            .flat_map(|(observer, records)| {
+                records
+                    .records
+                    .iter()
+                    .map(|(subject, (status, version))| ReachabilityRecord {
+                        observer: observer.clone(),
+                        subject: subject.clone(),
+                        status: *status,
+                        version: *version,
+                    })
+            })
+            .collect()
+    }
+
+    fn change(&mut self, observer: &I, subject: &I, status: ReachabilityStatus) {
+        if observer == subject {
+            return;
+        }
+
+        let current = self.status_by(observer, subject);
+
+        if current == status || current == ReachabilityStatus::Terminated {
+            return;
+        }
+
+        let observer = self
+            .observers
+            .entry(observer.clone())
+            .or_insert_with(|| Observer {
+                version: 0,
+                records: HashMap::new(),
+            });
+
+        observer.version += 1;
+        observer
+            .records
+            .insert(subject.clone(), (status, observer.version));
+
+        let all_reachable = observer
+            .records
+            .values()
+            .all(|(status, _)| *status == ReachabilityStatus::Reachable);
+
+        if all_reachable {
+            observer.records.clear();
+        }
+    }
+}
+
+impl<I> Default for Reachability<I>
+where
+    I: Eq + Hash + Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, ReachabilityStatus::*};
+
+    #[test]
+    fn observer_records() {
+        let mut reachability = Reachability::new();
+
+        reachability.unreachable(&"a", &"b");
+        reachability.unreachable(&"a", &"b");
+        reachability.unreachable(&"a", &"c");
+        assert_eq!(reachability.version(&"a"), 2);
+        assert_eq!(reachability.status(&"b"), Unreachable);
+        assert_eq!(reachability.records().len(), 2);
+
+        // Records are pruned once everything is reachable again.
+        reachability.reachable(&"a", &"b");
+        assert_eq!(reachability.status(&"b"), Reachable);
+        assert_eq!(reachability.records().len(), 2);
+        reachability.reachable(&"a", &"c");
+        assert_eq!(reachability.version(&"a"), 4);
+        assert!(reachability.records().is_empty());
+
+        // Terminated is final.
+        reachability.terminated(&"a", &"b");
+        reachability.reachable(&"a", &"b");
+        assert_eq!(reachability.status(&"b"), Terminated);
+
+        // Observers can't record themselves.
+        reachability.unreachable(&"c", &"c");
+        assert_eq!(reachability.version(&"c"), 0);
+    }
+
+    #[test]
+    fn merging() {
+        let mut a = Reachability::new();
+        let mut b = Reachability::new();
+
+        a.unreachable(&"a", &"x");
+        b.unreachable(&"b", &"x");
+        b.unreachable(&"b", &"y");
+
+        // Stale view of the records of `b`.
+        let stale = b.clone();
+        b.reachable(&"b", &"y");
+
+        a.merge(&b);
+        a.merge(&stale);
+        assert_eq!(a.status_by(&"b", &"y"), Reachable);
+
+        let mut unreachable = a.observers_unreachable(&"x");
+        unreachable.sort();
+        assert_eq!(unreachable, ["a", "b"]);
+
+        // Merging is commutative.
+        b.merge(&a);
+        let mut records_a = a.records();
+        let mut records_b = b.records();
+        records_a.sort_by_key(|record| (record.observer, record.subject));
+        records_b.sort_by_key(|record| (record.observer, record.subject));
+        assert_eq!(records_a, records_b);
+    }
+
+    #[test]
+    fn aggregated_verdicts() {
+        let mut reachability = Reachability::new();
+        reachability.unreachable(&1, &0);
+        assert!(reachability.is_unreachable_by_any(&0));
+        assert!(!reachability.is_unreachable_by_majority(&0, 4));
+
+        reachability.unreachable(&2, &0);
+        assert!(!reachability.is_unreachable_by_majority(&0, 4));
+
+        reachability.unreachable(&3, &0);
+        assert!(reachability.is_unreachable_by_majority(&0, 4));
+
+        reachability.terminated(&4, &0);
+        assert_eq!(reachability.status(&0), Terminated);
+        assert_eq!(reachability.status(&1), Reachable);
+    }
+}
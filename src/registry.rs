@@ -0,0 +1,126 @@
+use {
+    crate::Detector,
+    std::{
+        collections::HashMap,
+        hash::Hash,
+        sync::{Arc, RwLock},
+        time::Duration,
+    },
+};
+
+/// Collection of [`Detector`]s keyed by the monitored resource, e.g. peer
+/// address.
+///
+/// Detectors are created lazily using the provided factory when the first
+/// heartbeat from a resource arrives.
+pub struct Registry<K, D> {
+    detectors: RwLock<HashMap<K, Arc<D>>>,
+    factory: Box<dyn Fn(&K) -> D + Send + Sync>,
+}
+
+impl<K, D> Registry<K, D>
+where
+    K: Eq + Hash + Clone,
+    D: Detector,
+{
+    /// Creates an empty registry, using `factory` to create a detector for each
+    /// new resource.
+    pub fn new(factory: impl Fn(&K) -> D + Send + Sync + 'static) -> Self {
+        Self {
+            detectors: Default::default(),
+            factory: Box::new(factory),
+        }
+    }
+
+    /// Records a heartbeat from the resource, starting to monitor it if it's
+    /// not monitored yet.
+    pub fn heartbeat(&self, key: &K) {
+        self.get_or_insert(key).heartbeat();
+    }
+
+    /// Returns `true` if the resource is considered to be up and healthy.
+    /// Resources that are not monitored are considered healthy.
+    pub fn is_available(&self, key: &K) -> bool {
+        self.get(key).is_none_or(|detector| detector.is_available())
+    }
+
+    /// Returns `true` if the resource is being monitored, i.e. at least one
+    /// heartbeat has been recorded.
+    pub fn is_monitoring(&self, key: &K) -> bool {
+        self.detectors.read().unwrap().contains_key(key)
+    }
+
+    /// Returns the detector of the resource, if it's being monitored.
+    pub fn get(&self, key: &K) -> Option<Arc<D>> {
+        self.detectors.read().unwrap().get(key).cloned()
+    }
+
+    /// Returns the detector of the resource, creating it if necessary.
+    pub fn get_or_insert(&self, key: &K) -> Arc<D> {
+        if let Some(detector) = self.get(key) {
+            return detector;
+        }
+
+        self.detectors
+            .write()
+            .unwrap()
+            .entry(key.clone())
+            .or_insert_with(|| Arc::new((self.factory)(key)))
+            .clone()
+    }
+
+    /// Suspends monitoring of the resource. See [`Detector::pause_monitoring`].
+    ///
+    /// Does nothing if the resource is not being monitored.
+    pub fn pause_monitoring(&self, key: &K) {
+        if let Some(detector) = self.get(key) {
+            detector.pause_monitoring();
+        }
+    }
+
+    /// Resumes monitoring of the resource. See
+    /// [`Detector::resume_monitoring`].
+    pub fn resume_monitoring(&self, key: &K) {
+        if let Some(detector) = self.get(key) {
+            detector.resume_monitoring();
+        }
+    }
+
+    /// Excludes a local stall from every monitored resource. See
+    /// [`Detector::exclude_stall`].
+    pub fn exclude_stall(&self, duration: Duration) {
+        for detector in self.detectors.read().unwrap().values() {
+            detector.exclude_stall(duration);
+        }
+    }
+
+    /// Stops monitoring the resource, returning its detector.
+    pub fn remove(&self, key: &K) -> Option<Arc<D>> {
+        self.detectors.write().unwrap().remove(key)
+    }
+
+    /// Stops monitoring all resources.
+    pub fn reset(&self) {
+        self.detectors.write().unwrap().clear();
+    }
+
+    /// Returns a snapshot of monitored resources and their detectors.
+    pub fn detectors(&self) -> Vec<(K, Arc<D>)> {
+        self.detectors
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(key, detector)| (key.clone(), detector.clone()))
+            .collect()
+    }
+
+    /// Number of monitored resources.
+    pub fn len(&self) -> usize {
+        self.detectors.read().unwrap().len()
+    }
+
+    /// Returns `true` if no resources are monitored.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
@@ -0,0 +1,288 @@
+//! Offline replay of recorded heartbeat arrivals, e.g. for tuning the detector
+//! configuration.
+//!
+//! Arrivals are replayed through a [`FailureDetector`] per peer using a
+//! [`ManualClock`]. Since phi only grows between heartbeats, the moments the
+//! detector starts suspecting a peer are found exactly, with millisecond
+//! resolution, rather than by sampling.
+//!
+//! A suspicion followed by another heartbeat from the peer is a false
+//! positive. A suspicion of a peer that has stopped sending heartbeats before
+//! the end of the trace is a detection, and the time from its last heartbeat
+//! to the suspicion is the detection time.
+
+use {
+    crate::{
+        qos::Trace,
+        Config,
+        Detector,
+        Error,
+        FailureDetector,
+        ManualClock,
+        UnsyncDetector,
+        UnsyncState,
+    },
+    std::{collections::BTreeMap, time::Duration},
+};
+
+const RESOLUTION: Duration = Duration::from_millis(1);
+
+/// Heartbeat arrival from a peer, at a time relative to the start of the trace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Arrival {
+    pub peer: String,
+    pub time: Duration,
+}
+
+/// Period the detector was suspecting a peer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suspicion {
+    pub start: Duration,
+
+    /// Arrival of the heartbeat ending the suspicion, if any.
+    pub end: Option<Duration>,
+}
+
+impl Suspicion {
+    /// Returns `true` if the peer has sent a heartbeat after the suspicion.
+    pub fn is_false_positive(&self) -> bool {
+        self.end.is_some()
+    }
+}
+
+/// Replay results of a single peer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PeerReport {
+    pub peer: String,
+    pub heartbeats: usize,
+    pub first_heartbeat: Duration,
+    pub last_heartbeat: Duration,
+    pub suspicions: Vec<Suspicion>,
+
+    /// Phi sampled at the configured interval, if any.
+    pub phi: Vec<(Duration, f64)>,
+}
+
+impl PeerReport {
+    /// Returns the number of suspicions followed by a heartbeat.
+    pub fn false_positives(&self) -> usize {
+        self.suspicions
+            .iter()
+            .filter(|suspicion| suspicion.is_false_positive())
+            .count()
+    }
+
+    /// Returns the total duration of false positive suspicions.
+    pub fn mistake_duration(&self) -> Duration {
+        self.suspicions
+            .iter()
+            .filter_map(|suspicion| Some(suspicion.end? - suspicion.start))
+            .sum()
+    }
+
+    /// Returns the time from the last heartbeat to the suspicion, if the peer
+    /// has been suspected after it stopped sending heartbeats.
+    pub fn detection_time(&self) -> Option<Duration> {
+        let suspicion = self.suspicions.last()?;
+
+        if suspicion.is_false_positive() {
+            None
+        } else {
+            Some(suspicion.start - self.last_heartbeat)
+        }
+    }
+
+    /// Returns a QoS [`Trace`] of the detector verdicts. The peer is assumed to
+    /// be up, crashes are to be recorded by the caller.
+    pub fn trace(&self) -> Trace {
+        let mut trace = Trace::new();
+
+        for suspicion in &self.suspicions {
+            trace.record(suspicion.start, false);
+
+            if let Some(end) = suspicion.end {
+                trace.record(end, true);
+            }
+        }
+
+        trace
+    }
+}
+
+/// Replay of heartbeat arrivals with a particular detector configuration.
+#[derive(Debug, Clone)]
+pub struct Replay {
+    config: Config,
+    end: Option<Duration>,
+    sample_interval: Option<Duration>,
+}
+
+impl Replay {
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            end: None,
+            sample_interval: None,
+        }
+    }
+
+    /// End of the trace, which peers that stopped sending heartbeats may be
+    /// detected until.
+    ///
+    /// Default: the last arrival of all peers
+    pub fn end(mut self, end: Duration) -> Self {
+        self.end = Some(end);
+        self
+    }
+
+    /// Interval to sample phi at for [`PeerReport::phi`].
+    ///
+    /// Default: None, phi is not sampled
+    pub fn sample_interval(mut self, sample_interval: Duration) -> Self {
+        self.sample_interval = Some(sample_interval).filter(|interval| !interval.is_zero());
+        self
+    }
+
+    /// Replays the arrivals, returning the reports of all peers ordered by
+    /// name.
+    ///
+    /// Returns an [`Error`] if the configuration is invalid.
+    pub fn run(
+        &self,
+        arrivals: impl IntoIterator<Item = Arrival>,
+    ) -> Result<Vec<PeerReport>, Error> {
+        let mut peers: BTreeMap<String, Vec<Duration>> = BTreeMap::new();
+
+        for arrival in arrivals {
+            peers.entry(arrival.peer).or_default().push(arrival.time);
+        }
+
+        let end = self
+            .end
+            .or_else(|| peers.values().flatten().max().copied())
+            .unwrap_or_default();
+
+        peers
+            .into_iter()
+            .map(|(peer, mut times)| {
+                times.sort();
+                self.replay_peer(peer, &times, end)
+            })
+            .collect()
+    }
+
+    fn replay_peer(
+        &self,
+        peer: String,
+        times: &[Duration],
+        end: Duration,
+    ) -> Result<PeerReport, Error> {
+        let clock = ManualClock::new();
+        let detector = UnsyncDetector::builder()
+            .config(self.config.clone())
+            .clock(clock.clone())
+            .build()?;
+
+        let mut replay = PeerReplay {
+            clock,
+            detector,
+            sample_interval: self.sample_interval,
+            next_sample: times[0],
+            suspicions: Vec::new(),
+            phi: Vec::new(),
+        };
+
+        for (i, time) in times.iter().enumerate() {
+            if i > 0 {
+                // The heartbeat arrives at `time`, so the detector could only
+                // suspect the peer before that.
+                replay.advance(times[i - 1], *time - RESOLUTION.min(*time - times[i - 1]));
+            }
+
+            replay.heartbeat(*time);
+        }
+
+        // Safe unwrap, there's at least one arrival.
+        let last_heartbeat = *times.last().unwrap();
+        replay.advance(last_heartbeat, end.max(last_heartbeat));
+
+        Ok(PeerReport {
+            peer,
+            heartbeats: times.len(),
+            first_heartbeat: times[0],
+            last_heartbeat,
+            suspicions: replay.suspicions,
+            phi: replay.phi,
+        })
+    }
+}
+
+struct PeerReplay {
+    clock: ManualClock,
+    detector: FailureDetector<UnsyncState<ManualClock>>,
+    sample_interval: Option<Duration>,
+    next_sample: Duration,
+    suspicions: Vec<Suspicion>,
+    phi: Vec<(Duration, f64)>,
+}
+
+impl PeerReplay {
+    fn heartbeat(&mut self, time: Duration) {
+        self.sample(time);
+        self.clock.set(time);
+
+        if let Some(suspicion) = self.suspicions.last_mut() {
+            suspicion.end.get_or_insert(time);
+        }
+
+        self.detector.heartbeat();
+    }
+
+    /// Advances time from the last heartbeat at `from` up to and including
+    /// `to`, recording the suspicion if the detector starts suspecting the
+    /// peer.
+    fn advance(&mut self, from: Duration, to: Duration) {
+        self.sample(to + RESOLUTION);
+
+        if self.is_available_at(to) {
+            return;
+        }
+
+        // Phi grows monotonically since the last heartbeat, find the first
+        // moment the peer is suspected.
+        let (mut low, mut high) = (from, to);
+
+        while high - low > RESOLUTION {
+            let mid = low + (high - low) / 2;
+
+            if self.is_available_at(mid) {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+
+        self.suspicions.push(Suspicion {
+            start: high,
+            end: None,
+        });
+    }
+
+    /// Samples phi up to, but not including, `until`.
+    fn sample(&mut self, until: Duration) {
+        let Some(interval) = self.sample_interval else {
+            return;
+        };
+
+        while self.next_sample < until {
+            self.clock.set(self.next_sample);
+            self.phi.push((self.next_sample, self.detector.phi()));
+            self.next_sample += interval;
+        }
+    }
+
+    fn is_available_at(&self, time: Duration) -> bool {
+        self.clock.set(time);
+        self.detector.is_available()
+    }
+}
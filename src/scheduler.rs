@@ -0,0 +1,250 @@
+use {
+    crate::{rng::Rng, Clock, DefaultClock},
+    std::time::Duration,
+};
+
+/// Schedule of outgoing heartbeats.
+///
+/// Heartbeat deadlines are produced by iterating the scheduler, as offsets from
+/// the moment the scheduler was created. Consecutive deadlines are the
+/// interval apart, with random jitter applied, and don't drift when sending is
+/// late. Recording sent heartbeats with [`HeartbeatScheduler::record_sent`]
+/// tracks how late the sender is, e.g. due to local load.
+///
+/// The interval can adapt to the suspicion level of the sender observed by a
+/// peer, reported with [`HeartbeatScheduler::observe_phi`]: it's halved when
+/// phi rises above the speed-up level, and grows slowly back while phi stays
+/// low, within the `[min_interval, max_interval]` bounds.
+///
+/// ```
+/// use {phi_accrual_failure_detector::HeartbeatScheduler, std::time::Duration};
+///
+/// let mut scheduler = HeartbeatScheduler::new(Duration::from_millis(10));
+///
+/// for _deadline in scheduler.by_ref().take(3) {
+///     // Sleep until the deadline and send the heartbeat.
+/// }
+///
+/// assert_eq!(scheduler.deadline(), Duration::from_millis(30));
+/// ```
+pub struct HeartbeatScheduler<C: Clock = DefaultClock> {
+    clock: C,
+    start: C::Timestamp,
+    interval: Duration,
+    min_interval: Duration,
+    max_interval: Duration,
+    jitter: Duration,
+    speedup_phi: f64,
+    deadline: Duration,
+    last_deadline: Option<Duration>,
+    rng: Rng,
+    stats: SchedulerStats,
+}
+
+/// Statistics of the heartbeats sent by a [`HeartbeatScheduler`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SchedulerStats {
+    /// Number of heartbeats recorded as sent.
+    pub sent: u64,
+
+    /// Lateness of the most recent heartbeat relative to its deadline.
+    pub last_lateness: Duration,
+
+    /// Maximum lateness of all heartbeats.
+    pub max_lateness: Duration,
+
+    /// Mean lateness of all heartbeats.
+    pub mean_lateness: Duration,
+
+    /// Current interval between heartbeats, before jitter.
+    pub interval: Duration,
+}
+
+impl HeartbeatScheduler {
+    /// Creates a scheduler with the first deadline due immediately.
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            clock: DefaultClock,
+            start: DefaultClock.timestamp(),
+            interval,
+            min_interval: interval,
+            max_interval: interval,
+            jitter: Duration::ZERO,
+            speedup_phi: 1.0,
+            deadline: Duration::ZERO,
+            last_deadline: None,
+            rng: Rng::from_entropy(),
+            stats: SchedulerStats {
+                interval,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl<C: Clock> HeartbeatScheduler<C> {
+    /// Maximum random deviation from the interval, to avoid synchronized
+    /// bursts of heartbeats from many senders.
+    ///
+    /// Default: 0
+    pub fn jitter(mut self, jitter: Duration) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Lower bound of the adaptive interval.
+    ///
+    /// Default: same as interval, i.e. no adaptation
+    pub fn min_interval(mut self, min_interval: Duration) -> Self {
+        self.min_interval = min_interval.min(self.interval);
+        self
+    }
+
+    /// Upper bound of the adaptive interval.
+    ///
+    /// Default: same as interval, i.e. no adaptation
+    pub fn max_interval(mut self, max_interval: Duration) -> Self {
+        self.max_interval = max_interval.max(self.interval);
+        self
+    }
+
+    /// Phi observed by a peer at which the interval is halved. The interval
+    /// grows while phi stays below half of this level.
+    ///
+    /// Default: 1.0
+    pub fn speedup_phi(mut self, speedup_phi: f64) -> Self {
+        self.speedup_phi = speedup_phi;
+        self
+    }
+
+    /// Seed of the jitter. Defaults to a random seed.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.rng = Rng::new(seed);
+        self
+    }
+
+    /// Provide an alternative implementation of [`Clock`]. Deadlines are
+    /// measured from the moment the clock is set.
+    ///
+    /// Default: [`DefaultClock`]
+    pub fn clock<T: Clock>(self, clock: T) -> HeartbeatScheduler<T> {
+        HeartbeatScheduler {
+            start: clock.timestamp(),
+            clock,
+            interval: self.interval,
+            min_interval: self.min_interval,
+            max_interval: self.max_interval,
+            jitter: self.jitter,
+            speedup_phi: self.speedup_phi,
+            deadline: self.deadline,
+            last_deadline: self.last_deadline,
+            rng: self.rng,
+            stats: self.stats,
+        }
+    }
+
+    /// Returns the next deadline, as an offset from the moment the scheduler
+    /// was created.
+    pub fn deadline(&self) -> Duration {
+        self.deadline
+    }
+
+    /// Returns the time elapsed since the scheduler was created.
+    pub fn elapsed(&self) -> Duration {
+        C::elapsed(&self.start, &self.clock.timestamp())
+    }
+
+    /// Returns the time remaining until the deadline, zero if it's due.
+    pub fn delay_until(&self, deadline: Duration) -> Duration {
+        deadline.saturating_sub(self.elapsed())
+    }
+
+    /// Returns the interval until the following heartbeat, i.e. the current
+    /// interval with random jitter applied.
+    pub fn next_interval(&mut self) -> Duration {
+        let jitter = self.jitter.as_secs_f64() * (self.rng.next_f64() * 2. - 1.);
+        Duration::from_secs_f64((self.interval.as_secs_f64() + jitter).max(0.))
+    }
+
+    /// Records that the heartbeat for the most recently produced deadline has
+    /// been sent, returning how late it was.
+    pub fn record_sent(&mut self) -> Duration {
+        let deadline = self.last_deadline.unwrap_or(self.deadline);
+        let lateness = self.elapsed().saturating_sub(deadline);
+        let stats = &mut self.stats;
+
+        stats.sent += 1;
+        stats.last_lateness = lateness;
+        stats.max_lateness = stats.max_lateness.max(lateness);
+        stats.mean_lateness = Duration::from_secs_f64(
+            stats.mean_lateness.as_secs_f64()
+                + (lateness.as_secs_f64() - stats.mean_lateness.as_secs_f64()) / stats.sent as f64,
+        );
+
+        lateness
+    }
+
+    /// Adapts the interval to the suspicion level of the local node observed by
+    /// a peer.
+    pub fn observe_phi(&mut self, phi: f64) {
+        let interval = if phi >= self.speedup_phi {
+            self.interval / 2
+        } else if phi < self.speedup_phi / 2. {
+            self.interval * 5 / 4
+        } else {
+            self.interval
+        };
+
+        self.interval = interval.clamp(self.min_interval, self.max_interval);
+        self.stats.interval = self.interval;
+    }
+
+    /// Returns the statistics of the sent heartbeats.
+    pub fn stats(&self) -> SchedulerStats {
+        self.stats.clone()
+    }
+}
+
+/// Infinite iterator of heartbeat deadlines. Deadlines that have already been
+/// missed by more than an interval are skipped, so a stalled sender doesn't
+/// send a burst of heartbeats to catch up.
+impl<C: Clock> Iterator for HeartbeatScheduler<C> {
+    type Item = Duration;
+
+    fn next(&mut self) -> Option<Duration> {
+        let deadline = self.deadline;
+        let interval = self.next_interval();
+        let elapsed = self.elapsed();
+
+        self.deadline = if deadline + interval < elapsed {
+            elapsed + interval
+        } else {
+            deadline + interval
+        };
+        self.last_deadline = Some(deadline);
+
+        Some(deadline)
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<C> HeartbeatScheduler<C>
+where
+    C: Clock + Send + 'static,
+    C::Timestamp: Send,
+{
+    /// Calls `send` at every deadline on a tokio task, until the task is
+    /// aborted. The scheduler is passed to `send`, e.g. to adapt the interval.
+    pub fn spawn(
+        mut self,
+        mut send: impl FnMut(&mut Self) + Send + 'static,
+    ) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            while let Some(deadline) = self.next() {
+                tokio::time::sleep(self.delay_until(deadline)).await;
+                send(&mut self);
+                self.record_sent();
+            }
+        })
+    }
+}
@@ -0,0 +1,356 @@
+//! Deterministic simulation of heartbeats sent over a lossy link.
+//!
+//! A [`Link`] models the heartbeats of a single peer: sent at a fixed interval,
+//! delayed by a base latency plus random jitter, and lost at random, in random
+//! bursts, during partitions or after the peer crashes. A [`Simulation`]
+//! delivers them to a [`Detector`] driven by a [`ManualClock`], step by step,
+//! and records phi over time, so that detector behavior is reproducible for a
+//! given seed without sleeping in tests.
+//!
+//! ```
+//! use {
+//!     phi_accrual_failure_detector::{
+//!         sim::{Jitter, Link, Simulation},
+//!         UnsyncDetector,
+//!     },
+//!     std::time::Duration,
+//! };
+//!
+//! let link = Link::new(Duration::from_secs(1))
+//!     .jitter(Jitter::Normal {
+//!         std_deviation: Duration::from_millis(50),
+//!     })
+//!     .crash(Duration::from_secs(60));
+//!
+//! let mut sim = Simulation::new(link).seed(7);
+//! let detector = UnsyncDetector::builder()
+//!     .clock(sim.clock())
+//!     .build()
+//!     .unwrap();
+//!
+//! sim.run_until(&detector, Duration::from_secs(80));
+//!
+//! let qos = sim.trace().qos(sim.now());
+//! assert_eq!(qos.undetected, 0);
+//! ```
+
+use {
+    crate::{qos::Trace, rng::Rng, Detector, ManualClock},
+    std::{cmp::Reverse, collections::BinaryHeap, f64::consts::TAU, time::Duration},
+};
+
+/// Distribution of the random delay added to the link latency.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Jitter {
+    None,
+
+    /// Normally distributed delay with zero mean. Heartbeats never arrive
+    /// before they're sent.
+    Normal {
+        std_deviation: Duration,
+    },
+
+    /// Heavy-tailed delay following the Pareto distribution shifted to start at
+    /// zero (Lomax), with the given scale and shape. The lower the shape, the
+    /// heavier the tail.
+    Pareto {
+        scale: Duration,
+        shape: f64,
+    },
+}
+
+impl Jitter {
+    fn sample(&self, latency: Duration, rng: &mut Rng) -> Duration {
+        let delay = latency.as_secs_f64()
+            + match *self {
+                Jitter::None => 0.,
+                Jitter::Normal { std_deviation } => {
+                    // Box-Muller transform.
+                    let (u, v) = (1. - rng.next_f64(), rng.next_f64());
+                    std_deviation.as_secs_f64() * (-2. * u.ln()).sqrt() * (TAU * v).cos()
+                }
+                Jitter::Pareto { scale, shape } => {
+                    scale.as_secs_f64() * ((1. - rng.next_f64()).powf(-1. / shape) - 1.)
+                }
+            };
+
+        Duration::try_from_secs_f64(delay.max(0.)).unwrap_or(Duration::MAX)
+    }
+}
+
+/// Model of the heartbeats of a single peer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Link {
+    interval: Duration,
+    latency: Duration,
+    jitter: Jitter,
+    loss: f64,
+    burst: Option<(f64, Duration)>,
+    partitions: Vec<(Duration, Duration)>,
+    crash: Option<Duration>,
+}
+
+impl Link {
+    /// Creates a link with heartbeats sent every `interval`, starting at the
+    /// start of the simulation.
+    ///
+    /// Panics if `interval` is zero.
+    pub fn new(interval: Duration) -> Self {
+        assert!(!interval.is_zero(), "heartbeat interval must be > 0");
+
+        Self {
+            interval,
+            latency: Duration::ZERO,
+            jitter: Jitter::None,
+            loss: 0.,
+            burst: None,
+            partitions: Vec::new(),
+            crash: None,
+        }
+    }
+
+    /// Base delay of every heartbeat.
+    ///
+    /// Default: 0
+    pub fn latency(mut self, latency: Duration) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    /// Random delay added to the latency of every heartbeat.
+    ///
+    /// Default: [`Jitter::None`]
+    pub fn jitter(mut self, jitter: Jitter) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Probability of losing each heartbeat independently.
+    ///
+    /// Default: 0
+    pub fn loss(mut self, probability: f64) -> Self {
+        self.loss = probability;
+        self
+    }
+
+    /// Probability of an outage starting at each heartbeat, losing all
+    /// heartbeats sent within `length`.
+    ///
+    /// Default: None
+    pub fn burst_loss(mut self, probability: f64, length: Duration) -> Self {
+        self.burst = Some((probability, length));
+        self
+    }
+
+    /// Loses all heartbeats sent from `start` until `end`.
+    pub fn partition(mut self, start: Duration, end: Duration) -> Self {
+        self.partitions.push((start, end));
+        self
+    }
+
+    /// Stops sending heartbeats at `at`. Heartbeats already in flight are
+    /// still delivered.
+    ///
+    /// Default: None, the peer never crashes
+    pub fn crash(mut self, at: Duration) -> Self {
+        self.crash = Some(at);
+        self
+    }
+
+    fn is_partitioned(&self, time: Duration) -> bool {
+        self.partitions
+            .iter()
+            .any(|(start, end)| (*start..*end).contains(&time))
+    }
+}
+
+/// Phi and availability of the peer at a moment of the simulation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sample {
+    pub time: Duration,
+    pub phi: f64,
+    pub available: bool,
+}
+
+/// Event processed by [`Simulation::step`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Event {
+    /// A heartbeat arrived and was delivered to the detector.
+    Heartbeat(Duration),
+
+    /// Phi was sampled.
+    Sample(Sample),
+}
+
+/// Simulation of heartbeats sent over a [`Link`] to a detector.
+///
+/// The detector must use the [`ManualClock`] returned by
+/// [`Simulation::clock`].
+#[derive(Debug)]
+pub struct Simulation {
+    link: Link,
+    clock: ManualClock,
+    rng: Rng,
+    sample_interval: Duration,
+    now: Duration,
+    next_send: Duration,
+    next_sample: Duration,
+    outage_until: Duration,
+    in_flight: BinaryHeap<Reverse<Duration>>,
+    arrivals: Vec<Duration>,
+    samples: Vec<Sample>,
+    trace: Trace,
+}
+
+impl Simulation {
+    pub fn new(link: Link) -> Self {
+        let mut trace = Trace::new();
+
+        if let Some(crash) = link.crash {
+            trace.crash(crash);
+        }
+
+        Self {
+            link,
+            clock: ManualClock::new(),
+            rng: Rng::new(0),
+            sample_interval: Duration::from_millis(100),
+            now: Duration::ZERO,
+            next_send: Duration::ZERO,
+            next_sample: Duration::ZERO,
+            outage_until: Duration::ZERO,
+            in_flight: BinaryHeap::new(),
+            arrivals: Vec::new(),
+            samples: Vec::new(),
+            trace,
+        }
+    }
+
+    /// Seed of the random jitter and losses.
+    ///
+    /// Default: 0
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.rng = Rng::new(seed);
+        self
+    }
+
+    /// Interval to sample phi at.
+    ///
+    /// Default: 100ms
+    ///
+    /// Panics if `sample_interval` is zero.
+    pub fn sample_interval(mut self, sample_interval: Duration) -> Self {
+        assert!(!sample_interval.is_zero(), "sample interval must be > 0");
+        self.sample_interval = sample_interval;
+        self
+    }
+
+    /// Clock to build the simulated detector with.
+    pub fn clock(&self) -> ManualClock {
+        self.clock.clone()
+    }
+
+    /// Time of the last processed event, since the start of the simulation.
+    pub fn now(&self) -> Duration {
+        self.now
+    }
+
+    /// Arrival times of the heartbeats delivered so far.
+    pub fn arrivals(&self) -> &[Duration] {
+        &self.arrivals
+    }
+
+    /// Phi sampled so far, at the configured interval.
+    pub fn samples(&self) -> &[Sample] {
+        &self.samples
+    }
+
+    /// QoS [`Trace`] of the crash of the peer, if any, and the verdicts of the
+    /// detector at every processed event.
+    pub fn trace(&self) -> &Trace {
+        &self.trace
+    }
+
+    /// Advances the clock to the next heartbeat arrival or phi sample, and
+    /// processes it. Heartbeats are processed before samples at the same time.
+    pub fn step(&mut self, detector: &impl Detector) -> Event {
+        // Heartbeats sent later than the next sample can't arrive before it.
+        self.send_until(self.next_sample);
+
+        match self.in_flight.peek() {
+            Some(Reverse(arrival)) if *arrival <= self.next_sample => {
+                let arrival = *arrival;
+                self.in_flight.pop();
+                self.advance(arrival);
+
+                detector.heartbeat();
+                self.arrivals.push(arrival);
+                self.trace.observe(arrival, detector);
+
+                Event::Heartbeat(arrival)
+            }
+
+            _ => {
+                let time = self.next_sample;
+                self.next_sample += self.sample_interval;
+                self.advance(time);
+
+                let sample = Sample {
+                    time,
+                    phi: detector.phi(),
+                    available: detector.is_available(),
+                };
+
+                self.samples.push(sample);
+                self.trace.record(time, sample.available);
+
+                Event::Sample(sample)
+            }
+        }
+    }
+
+    /// Processes all events up to and including `until`.
+    pub fn run_until(&mut self, detector: &impl Detector, until: Duration) {
+        while self.next_event() <= until {
+            self.step(detector);
+        }
+    }
+
+    fn next_event(&mut self) -> Duration {
+        self.send_until(self.next_sample);
+
+        match self.in_flight.peek() {
+            Some(Reverse(arrival)) => self.next_sample.min(*arrival),
+            None => self.next_sample,
+        }
+    }
+
+    fn advance(&mut self, time: Duration) {
+        self.now = time;
+        self.clock.set(time);
+    }
+
+    /// Sends the heartbeats due until `until`.
+    fn send_until(&mut self, until: Duration) {
+        while self.next_send <= until && self.link.crash.is_none_or(|crash| self.next_send < crash)
+        {
+            let send = self.next_send;
+            self.next_send += self.link.interval;
+
+            if let Some((probability, length)) = self.link.burst {
+                if send >= self.outage_until && self.rng.next_f64() < probability {
+                    self.outage_until = send + length;
+                }
+            }
+
+            let lost = send < self.outage_until
+                || self.link.is_partitioned(send)
+                || self.rng.next_f64() < self.link.loss;
+
+            if !lost {
+                let delay = self.link.jitter.sample(self.link.latency, &mut self.rng);
+                self.in_flight.push(Reverse(send.saturating_add(delay)));
+            }
+        }
+    }
+}
@@ -0,0 +1,310 @@
+//! Heartbeat framing for connection-oriented transports.
+//!
+//! Heartbeats are interleaved with application data on an existing byte
+//! stream using length-delimited framing. Each message is prefixed with a
+//! big-endian `u32` length of the rest of the message, followed by a kind byte
+//! and the body:
+//!
+//! | Kind | Body                             |
+//! |------|----------------------------------|
+//! | `0`  | Application data, opaque         |
+//! | `1`  | Heartbeat [`Frame`]              |
+//!
+//! [`StreamReader`] and [`StreamWriter`] adapt blocking [`Read`] and [`Write`]
+//! streams, and with the `codec` feature enabled, `HeartbeatCodec`
+//! implements `tokio_util::codec` traits. Decoded heartbeats are recorded into
+//! a [`Detector`].
+
+#[cfg(feature = "codec")]
+pub use codec::HeartbeatCodec;
+use {
+    crate::{
+        wire::{Frame, FrameError},
+        Detector,
+    },
+    std::io::{self, Read, Write},
+};
+
+const KIND_DATA: u8 = 0;
+const KIND_HEARTBEAT: u8 = 1;
+
+/// Length of the message length prefix.
+const PREFIX_LEN: usize = 4;
+
+/// Maximum length of a message, excluding the length prefix.
+pub const MAX_MESSAGE_LEN: usize = 16 * 1024 * 1024;
+
+/// Outgoing message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Message<'a> {
+    /// Application data.
+    Data(&'a [u8]),
+
+    /// Heartbeat.
+    Heartbeat(Frame<'a>),
+}
+
+/// Decoded incoming message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Item {
+    /// Application data.
+    Data(Vec<u8>),
+
+    /// Heartbeat, which has already been recorded into the detector.
+    Heartbeat(HeartbeatFrame),
+}
+
+/// Owned, validated heartbeat [`Frame`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeartbeatFrame(Vec<u8>);
+
+impl HeartbeatFrame {
+    /// Returns the decoded frame.
+    pub fn frame(&self) -> Frame<'_> {
+        // Safe unwrap, the frame is validated on construction.
+        Frame::decode(&self.0).unwrap()
+    }
+}
+
+impl Message<'_> {
+    /// Appends the encoded message to `dst`.
+    pub fn encode(&self, dst: &mut Vec<u8>) -> io::Result<()> {
+        let (kind, body_len) = match self {
+            Self::Data(data) => (KIND_DATA, data.len()),
+            Self::Heartbeat(frame) => (KIND_HEARTBEAT, frame.encoded_len()),
+        };
+
+        let len = body_len + 1;
+
+        if len > MAX_MESSAGE_LEN {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "message is too large",
+            ));
+        }
+
+        dst.reserve(PREFIX_LEN + len);
+        dst.extend_from_slice(&(len as u32).to_be_bytes());
+        dst.push(kind);
+
+        match self {
+            Self::Data(data) => dst.extend_from_slice(data),
+            Self::Heartbeat(frame) => {
+                let offset = dst.len();
+                dst.resize(offset + body_len, 0);
+                frame.encode(&mut dst[offset..]).map_err(invalid_input)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the length of the message at the beginning of `buf`, excluding the
+/// length prefix, if the prefix is complete.
+fn message_len(buf: &[u8]) -> io::Result<Option<usize>> {
+    let Some(prefix) = buf.get(..PREFIX_LEN) else {
+        return Ok(None);
+    };
+
+    let len = u32::from_be_bytes(prefix.try_into().unwrap()) as usize;
+
+    if len == 0 || len > MAX_MESSAGE_LEN {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("invalid message length: {len}"),
+        ));
+    }
+
+    Ok(Some(len))
+}
+
+/// Decodes the message (without length prefix), recording heartbeats into the
+/// `detector`.
+fn decode_message(message: &[u8], detector: &impl Detector) -> io::Result<Item> {
+    // Safe indexing, the message length is checked to be non-zero.
+    let (kind, body) = (message[0], &message[1..]);
+
+    match kind {
+        KIND_DATA => Ok(Item::Data(body.to_vec())),
+
+        KIND_HEARTBEAT => {
+            Frame::decode(body).map_err(invalid_data)?;
+            detector.heartbeat();
+            Ok(Item::Heartbeat(HeartbeatFrame(body.to_vec())))
+        }
+
+        kind => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("unknown message kind: {kind}"),
+        )),
+    }
+}
+
+fn invalid_data(err: FrameError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+fn invalid_input(err: FrameError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, err)
+}
+
+/// Reads messages from a blocking stream, recording heartbeats into a
+/// [`Detector`].
+pub struct StreamReader<R, D> {
+    inner: R,
+    detector: D,
+}
+
+impl<R: Read, D: Detector> StreamReader<R, D> {
+    pub fn new(inner: R, detector: D) -> Self {
+        Self { inner, detector }
+    }
+
+    /// Reads the next message, returning `None` if the stream has ended
+    /// between messages. A stream ending within a message, including its
+    /// length prefix, is an [`io::ErrorKind::UnexpectedEof`] error.
+    pub fn read_item(&mut self) -> io::Result<Option<Item>> {
+        let mut prefix = [0; PREFIX_LEN];
+
+        let read = loop {
+            match self.inner.read(&mut prefix) {
+                Ok(0) => return Ok(None),
+                Ok(read) => break read,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        };
+
+        self.inner.read_exact(&mut prefix[read..])?;
+
+        // Safe unwrap, the prefix is complete.
+        let len = message_len(&prefix)?.unwrap();
+        let mut message = vec![0; len];
+        self.inner.read_exact(&mut message)?;
+
+        decode_message(&message, &self.detector).map(Some)
+    }
+
+    /// Returns the detector heartbeats are recorded into.
+    pub fn detector(&self) -> &D {
+        &self.detector
+    }
+
+    /// Returns the underlying stream.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: Read, D: Detector> Iterator for StreamReader<R, D> {
+    type Item = io::Result<Item>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_item().transpose()
+    }
+}
+
+/// Writes messages to a blocking stream.
+pub struct StreamWriter<W> {
+    inner: W,
+    buf: Vec<u8>,
+}
+
+impl<W: Write> StreamWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            buf: Vec::new(),
+        }
+    }
+
+    /// Writes a single message.
+    pub fn write_message(&mut self, message: Message<'_>) -> io::Result<()> {
+        self.buf.clear();
+        message.encode(&mut self.buf)?;
+        self.inner.write_all(&self.buf)
+    }
+
+    /// Writes application data.
+    pub fn write_data(&mut self, data: &[u8]) -> io::Result<()> {
+        self.write_message(Message::Data(data))
+    }
+
+    /// Writes a heartbeat.
+    pub fn write_heartbeat(&mut self, frame: Frame<'_>) -> io::Result<()> {
+        self.write_message(Message::Heartbeat(frame))
+    }
+
+    /// Flushes the underlying stream.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+
+    /// Returns the underlying stream.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+#[cfg(feature = "codec")]
+mod codec {
+    use {
+        super::*,
+        bytes::{Buf, BufMut, BytesMut},
+        tokio_util::codec::{Decoder, Encoder},
+    };
+
+    /// [`tokio_util::codec`] implementation of the stream framing, recording
+    /// decoded heartbeats into a [`Detector`].
+    pub struct HeartbeatCodec<D> {
+        detector: D,
+        buf: Vec<u8>,
+    }
+
+    impl<D: Detector> HeartbeatCodec<D> {
+        pub fn new(detector: D) -> Self {
+            Self {
+                detector,
+                buf: Vec::new(),
+            }
+        }
+
+        /// Returns the detector heartbeats are recorded into.
+        pub fn detector(&self) -> &D {
+            &self.detector
+        }
+    }
+
+    impl<D: Detector> Decoder for HeartbeatCodec<D> {
+        type Error = io::Error;
+        type Item = Item;
+
+        fn decode(&mut self, src: &mut BytesMut) -> io::Result<Option<Item>> {
+            let Some(len) = message_len(src)? else {
+                return Ok(None);
+            };
+
+            if src.len() < PREFIX_LEN + len {
+                src.reserve(PREFIX_LEN + len - src.len());
+                return Ok(None);
+            }
+
+            src.advance(PREFIX_LEN);
+            let message = src.split_to(len);
+
+            decode_message(&message, &self.detector).map(Some)
+        }
+    }
+
+    impl<D: Detector> Encoder<Message<'_>> for HeartbeatCodec<D> {
+        type Error = io::Error;
+
+        fn encode(&mut self, message: Message<'_>, dst: &mut BytesMut) -> io::Result<()> {
+            self.buf.clear();
+            message.encode(&mut self.buf)?;
+            dst.put_slice(&self.buf);
+            Ok(())
+        }
+    }
+}
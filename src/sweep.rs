@@ -0,0 +1,282 @@
+//! Parameter sweep over a recorded heartbeat trace.
+//!
+//! Every combination of the [`Grid`] parameters is evaluated by replaying the
+//! trace, see [`replay`](crate::replay):
+//!
+//! - Mistakes are the false positive suspicions of the unmodified trace,
+//!   reported as a rate per hour of monitoring of all peers.
+//! - Detection time is measured by injecting crashes into the trace: for every
+//!   crash point and every peer, the arrivals of the peer after the crash point
+//!   are removed, and the time from the last remaining heartbeat to the
+//!   suspicion is measured. The detector can't tell the crash from a silence
+//!   starting at that heartbeat, so a suspicion already ongoing at the crash
+//!   point doesn't count as an instant detection.
+//!
+//! The [`pareto_frontier`] of the results shows the trade-off between fast
+//! detection and mistakes, and [`recommend`] picks the configuration detecting
+//! crashes fastest while meeting a target mistake rate.
+
+use {
+    crate::{
+        replay::{Arrival, Replay},
+        Config,
+        Error,
+    },
+    std::{collections::BTreeMap, time::Duration},
+};
+
+/// Values of the configuration parameters to sweep.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Grid {
+    pub thresholds: Vec<f64>,
+    pub acceptable_heartbeat_pauses: Vec<Duration>,
+    pub max_sample_sizes: Vec<usize>,
+}
+
+impl Default for Grid {
+    fn default() -> Self {
+        Self {
+            thresholds: vec![1., 2., 3., 5., 8., 10., 12., 16.],
+            acceptable_heartbeat_pauses: [0, 500, 1000, 2000, 3000, 5000]
+                .into_iter()
+                .map(Duration::from_millis)
+                .collect(),
+            max_sample_sizes: vec![50, 100, 200, 1000],
+        }
+    }
+}
+
+impl Grid {
+    /// Returns all combinations of the parameters, with the rest of the
+    /// parameters taken from `base`.
+    pub fn configs(&self, base: &Config) -> Vec<Config> {
+        let mut configs = Vec::new();
+
+        for &threshold in &self.thresholds {
+            for &acceptable_heartbeat_pause in &self.acceptable_heartbeat_pauses {
+                for &max_sample_size in &self.max_sample_sizes {
+                    configs.push(Config {
+                        threshold,
+                        acceptable_heartbeat_pause,
+                        max_sample_size,
+                        ..base.clone()
+                    });
+                }
+            }
+        }
+
+        configs
+    }
+}
+
+/// Evaluation of a single configuration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SweepPoint {
+    pub config: Config,
+
+    /// Mean time from the last heartbeat before an injected crash to the
+    /// suspicion. Undetected crashes count as detected at the end of the trace.
+    pub mean_detection_time: Duration,
+
+    /// Maximum time from the last heartbeat before an injected crash to the
+    /// suspicion.
+    pub max_detection_time: Duration,
+
+    /// Number of injected crashes not detected by the end of the trace.
+    pub undetected: usize,
+
+    /// Number of false positive suspicions in the unmodified trace.
+    pub false_positives: usize,
+
+    /// False positive suspicions per hour of monitoring of all peers.
+    pub mistake_rate: f64,
+}
+
+impl SweepPoint {
+    /// Returns `true` if the point is at least as good as `other` in both
+    /// detection time and mistake rate, and better in at least one.
+    pub fn dominates(&self, other: &Self) -> bool {
+        let time = self.mean_detection_time.cmp(&other.mean_detection_time);
+        let rate = self.mistake_rate.total_cmp(&other.mistake_rate);
+
+        time.is_le() && rate.is_le() && (time.is_lt() || rate.is_lt())
+    }
+}
+
+/// Sweep of detector configurations over a heartbeat trace.
+#[derive(Debug, Clone)]
+pub struct Sweep {
+    grid: Grid,
+    base: Config,
+    crash_points: Vec<Duration>,
+}
+
+impl Sweep {
+    pub fn new(grid: Grid) -> Self {
+        Self {
+            grid,
+            base: Config::default(),
+            crash_points: Vec::new(),
+        }
+    }
+
+    /// Configuration the parameters not swept are taken from.
+    ///
+    /// Default: [`Config::default`]
+    pub fn base(mut self, base: Config) -> Self {
+        self.base = base;
+        self
+    }
+
+    /// Times to inject crashes at, relative to the start of the trace.
+    ///
+    /// Default: at a quarter, half and three quarters of the trace
+    pub fn crash_points(mut self, crash_points: Vec<Duration>) -> Self {
+        self.crash_points = crash_points;
+        self
+    }
+
+    /// Evaluates every configuration of the grid, in the order of
+    /// [`Grid::configs`].
+    ///
+    /// Returns an [`Error`] if some configuration is invalid.
+    pub fn run(&self, arrivals: &[Arrival]) -> Result<Vec<SweepPoint>, Error> {
+        let mut peers: BTreeMap<&str, Vec<Duration>> = BTreeMap::new();
+
+        for arrival in arrivals {
+            peers.entry(&arrival.peer).or_default().push(arrival.time);
+        }
+
+        let start = arrivals.iter().map(|arrival| arrival.time).min();
+        let end = arrivals.iter().map(|arrival| arrival.time).max();
+        let (Some(start), Some(end)) = (start, end) else {
+            return Ok(Vec::new());
+        };
+
+        let crash_points = if self.crash_points.is_empty() {
+            (1..=3).map(|i| start + (end - start) * i / 4).collect()
+        } else {
+            self.crash_points.clone()
+        };
+
+        let monitored: Duration = peers
+            .values()
+            .map(|times| {
+                let first = times.iter().min().unwrap();
+                let last = times.iter().max().unwrap();
+                end.max(*last) - *first
+            })
+            .sum();
+
+        self.grid
+            .configs(&self.base)
+            .into_iter()
+            .map(|config| {
+                let replay = Replay::new(config.clone()).end(end);
+                let false_positives = replay
+                    .run(arrivals.iter().cloned())?
+                    .iter()
+                    .map(|report| report.false_positives())
+                    .sum::<usize>();
+
+                let mut detection_times = Vec::new();
+                let mut undetected = 0;
+
+                for crash in &crash_points {
+                    for (peer, times) in &peers {
+                        let truncated: Vec<_> = times
+                            .iter()
+                            .filter(|time| *time <= crash)
+                            .map(|time| Arrival {
+                                peer: peer.to_string(),
+                                time: *time,
+                            })
+                            .collect();
+
+                        // Crashes before the peer has started aren't
+                        // meaningful.
+                        let Some(last_heartbeat) =
+                            truncated.iter().map(|arrival| arrival.time).max()
+                        else {
+                            continue;
+                        };
+
+                        let mut trace = replay.run(truncated)?[0].trace();
+                        trace.crash(last_heartbeat);
+
+                        // The replay detects crashes up to and including the
+                        // end of the trace.
+                        let qos = trace.qos(end.max(*crash) + Duration::from_millis(1));
+
+                        detection_times.push(qos.detection_times.first().copied().unwrap_or_else(
+                            || {
+                                undetected += 1;
+                                end.saturating_sub(last_heartbeat)
+                            },
+                        ));
+                    }
+                }
+
+                let hours = monitored.as_secs_f64() / 3600.;
+                let mistake_rate = if hours > 0. {
+                    false_positives as f64 / hours
+                } else {
+                    0.
+                };
+
+                Ok(SweepPoint {
+                    config,
+                    mean_detection_time: mean(&detection_times),
+                    max_detection_time: detection_times.iter().max().copied().unwrap_or_default(),
+                    undetected,
+                    false_positives,
+                    mistake_rate,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Returns the points not dominated by any other point, ordered by mean
+/// detection time.
+pub fn pareto_frontier(points: &[SweepPoint]) -> Vec<SweepPoint> {
+    let mut frontier: Vec<_> = points
+        .iter()
+        .filter(|point| !points.iter().any(|other| other.dominates(point)))
+        .cloned()
+        .collect();
+
+    frontier.sort_by(|a, b| {
+        a.mean_detection_time
+            .cmp(&b.mean_detection_time)
+            .then(a.mistake_rate.total_cmp(&b.mistake_rate))
+    });
+
+    // Keep a single point of equivalent configurations.
+    frontier.dedup_by(|a, b| {
+        a.mean_detection_time == b.mean_detection_time && a.mistake_rate == b.mistake_rate
+    });
+
+    frontier
+}
+
+/// Returns the point detecting all injected crashes fastest, with the mistake
+/// rate not exceeding `target_mistake_rate`.
+pub fn recommend(points: &[SweepPoint], target_mistake_rate: f64) -> Option<&SweepPoint> {
+    points
+        .iter()
+        .filter(|point| point.undetected == 0 && point.mistake_rate <= target_mistake_rate)
+        .min_by(|a, b| {
+            a.mean_detection_time
+                .cmp(&b.mean_detection_time)
+                .then(a.mistake_rate.total_cmp(&b.mistake_rate))
+        })
+}
+
+fn mean(durations: &[Duration]) -> Duration {
+    if durations.is_empty() {
+        Duration::ZERO
+    } else {
+        durations.iter().sum::<Duration>() / durations.len() as u32
+    }
+}
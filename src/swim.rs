@@ -0,0 +1,586 @@
+//! SWIM-style group membership.
+//!
+//! Each protocol period a node probes one member, chosen in shuffled
+//! round-robin order, with a direct ping. The probed member's [`Detector`]
+//! records a heartbeat on every ack, and once the direct probe has timed out
+//! and the detector no longer considers the member available, the node asks
+//! `k` other members to probe it on its behalf (ping-req). A member that
+//! answers neither the direct nor the indirect probes by the end of the period
+//! is suspected, and declared dead if the suspicion is not refuted in time.
+//!
+//! Membership updates are disseminated by piggybacking them on probe messages.
+//! Each update carries an incarnation number, which only the member itself
+//! increments, to refute suspicions about it.
+//!
+//! With [`Swim::local_health`] set, missed acks and suspicions of the local
+//! node are recorded as signs of local trouble, see [`LocalHealth`].
+//!
+//! The protocol is driven by the caller: [`Swim::tick`] advances the protocol
+//! by one tick, and incoming messages are passed to [`Swim::handle`].
+
+use {
+    crate::{rng::Rng, transport::Transport, Detector, LocalHealth},
+    std::{collections::HashMap, hash::Hash},
+};
+
+/// State of a member, as seen by the local node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemberState {
+    Alive,
+    Suspect,
+    Dead,
+}
+
+/// Membership update disseminated between nodes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Update<I> {
+    pub id: I,
+    pub incarnation: u64,
+    pub state: MemberState,
+}
+
+/// Protocol message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Message<I> {
+    /// Direct probe.
+    Ping { seq: u64, updates: Vec<Update<I>> },
+
+    /// Response to a direct probe, possibly forwarded by an intermediary.
+    Ack { seq: u64, updates: Vec<Update<I>> },
+
+    /// Request to probe `target` on behalf of the sender.
+    PingReq {
+        seq: u64,
+        target: I,
+        updates: Vec<Update<I>>,
+    },
+}
+
+/// Member as seen by the local node.
+pub struct Member<D> {
+    state: MemberState,
+    incarnation: u64,
+    suspected_at: u64,
+    detector: D,
+}
+
+impl<D> Member<D> {
+    pub fn state(&self) -> MemberState {
+        self.state
+    }
+
+    pub fn incarnation(&self) -> u64 {
+        self.incarnation
+    }
+
+    /// Returns the detector monitoring the member's acks.
+    pub fn detector(&self) -> &D {
+        &self.detector
+    }
+}
+
+struct Probe<I> {
+    target: I,
+    seq: u64,
+    started_at: u64,
+    acked: bool,
+    indirect: bool,
+}
+
+struct Forward<I> {
+    origin: I,
+    seq: u64,
+    created_at: u64,
+}
+
+struct Broadcast<I> {
+    update: Update<I>,
+    transmits: usize,
+}
+
+/// Local node of the SWIM membership protocol.
+pub struct Swim<I, D, T> {
+    id: I,
+    incarnation: u64,
+    transport: T,
+    factory: Box<dyn Fn(&I) -> D + Send + Sync>,
+    members: HashMap<I, Member<D>>,
+    // Members in the order they have been added, for deterministic iteration.
+    order: Vec<I>,
+    probe_order: Vec<I>,
+    probe: Option<Probe<I>>,
+    forwards: HashMap<u64, Forward<I>>,
+    broadcasts: Vec<Broadcast<I>>,
+    rng: Rng,
+    local_health: Option<LocalHealth>,
+    now: u64,
+    seq: u64,
+    protocol_period: u64,
+    probe_timeout: u64,
+    suspicion_timeout: u64,
+    indirect_probes: usize,
+    max_piggyback: usize,
+    retransmit_mult: usize,
+}
+
+impl<I, D, T> Swim<I, D, T>
+where
+    I: Eq + Hash + Clone,
+    D: Detector,
+    T: Transport<I, Message<I>>,
+{
+    /// Creates a node without any members, using `factory` to create a
+    /// detector for each new member.
+    pub fn new(id: I, transport: T, factory: impl Fn(&I) -> D + Send + Sync + 'static) -> Self {
+        let mut swim = Self {
+            id,
+            incarnation: 0,
+            transport,
+            factory: Box::new(factory),
+            members: HashMap::new(),
+            order: Vec::new(),
+            probe_order: Vec::new(),
+            probe: None,
+            forwards: HashMap::new(),
+            broadcasts: Vec::new(),
+            rng: Rng::from_entropy(),
+            local_health: None,
+            now: 0,
+            seq: 0,
+            protocol_period: 5,
+            probe_timeout: 2,
+            suspicion_timeout: 25,
+            indirect_probes: 3,
+            max_piggyback: 8,
+            retransmit_mult: 4,
+        };
+
+        swim.announce();
+        swim
+    }
+
+    /// Seed of the random member selection. Defaults to a random seed.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.rng = Rng::new(seed);
+        self
+    }
+
+    /// Length of the protocol period in ticks. Defaults to 5.
+    pub fn protocol_period(mut self, ticks: u64) -> Self {
+        self.protocol_period = ticks.max(1);
+        self
+    }
+
+    /// Number of ticks after which a direct probe times out. Defaults to 2.
+    pub fn probe_timeout(mut self, ticks: u64) -> Self {
+        self.probe_timeout = ticks;
+        self
+    }
+
+    /// Number of ticks a suspicion has to be refuted within before the member
+    /// is declared dead. Defaults to 25.
+    pub fn suspicion_timeout(mut self, ticks: u64) -> Self {
+        self.suspicion_timeout = ticks;
+        self
+    }
+
+    /// Number of members asked to probe an unresponsive member. Defaults to 3.
+    pub fn indirect_probes(mut self, k: usize) -> Self {
+        self.indirect_probes = k;
+        self
+    }
+
+    /// Maximum number of updates piggybacked on a single message. Defaults
+    /// to 8.
+    pub fn max_piggyback(mut self, max_piggyback: usize) -> Self {
+        self.max_piggyback = max_piggyback;
+        self
+    }
+
+    /// Multiplier of the number of times an update is piggybacked, which is
+    /// `retransmit_mult * ceil(log10(n + 1))` for a group of `n` members.
+    /// Defaults to 4.
+    pub fn retransmit_mult(mut self, retransmit_mult: usize) -> Self {
+        self.retransmit_mult = retransmit_mult.max(1);
+        self
+    }
+
+    /// Local health awareness updated by the protocol. Pass the same handle to
+    /// the detectors created by the factory to scale their threshold.
+    pub fn local_health(mut self, local_health: LocalHealth) -> Self {
+        self.local_health = Some(local_health);
+        self
+    }
+
+    /// Returns the identifier of the local node.
+    pub fn id(&self) -> &I {
+        &self.id
+    }
+
+    /// Returns the incarnation of the local node.
+    pub fn incarnation(&self) -> u64 {
+        self.incarnation
+    }
+
+    /// Returns the transport used to send messages.
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    /// Returns the member, excluding the local node.
+    pub fn member(&self, id: &I) -> Option<&Member<D>> {
+        self.members.get(id)
+    }
+
+    /// Returns the state of the member, excluding the local node.
+    pub fn state(&self, id: &I) -> Option<MemberState> {
+        self.member(id).map(Member::state)
+    }
+
+    /// Returns all known members, including dead ones, in the order they've
+    /// been discovered.
+    pub fn members(&self) -> impl Iterator<Item = (&I, &Member<D>)> {
+        self.order.iter().map(|id| (id, &self.members[id]))
+    }
+
+    /// Adds a member known to be alive, e.g. a seed node. The member learns
+    /// about the local node with the first probe.
+    pub fn join(&mut self, id: I) {
+        if id != self.id && !self.members.contains_key(&id) {
+            self.apply(Update {
+                id,
+                incarnation: 0,
+                state: MemberState::Alive,
+            });
+        }
+    }
+
+    /// Advances the protocol by one tick.
+    pub fn tick(&mut self) {
+        self.now += 1;
+        self.expire_suspicions();
+
+        let now = self.now;
+        let period = self.protocol_period;
+        self.forwards
+            .retain(|_, forward| now - forward.created_at < period);
+
+        if let Some(mut probe) = self.probe.take() {
+            let elapsed = now - probe.started_at;
+
+            if elapsed < period {
+                if !probe.acked && !probe.indirect && elapsed >= self.probe_timeout {
+                    let available = self
+                        .members
+                        .get(&probe.target)
+                        .is_none_or(|member| member.detector.is_available());
+
+                    if !available {
+                        self.probe_indirect(&probe);
+                        probe.indirect = true;
+                    }
+                }
+
+                self.probe = Some(probe);
+                return;
+            }
+
+            if let Some(local_health) = &self.local_health {
+                if probe.acked {
+                    local_health.record_success();
+                } else {
+                    local_health.record_failure();
+                }
+            }
+
+            // Only suspect the member if the detector has given up on it, and
+            // other members had a chance to reach it.
+            if !probe.acked && probe.indirect {
+                self.suspect(&probe.target);
+            }
+        }
+
+        self.start_probe();
+    }
+
+    /// Handles a message received from another node.
+    pub fn handle(&mut self, from: I, message: Message<I>) {
+        if from == self.id {
+            return;
+        }
+
+        // Receiving a message from an unknown node means it has joined.
+        self.join(from.clone());
+
+        let updates = match &message {
+            Message::Ping { updates, .. }
+            | Message::Ack { updates, .. }
+            | Message::PingReq { updates, .. } => updates.clone(),
+        };
+
+        for update in updates {
+            self.apply(update);
+        }
+
+        match message {
+            Message::Ping { seq, .. } => {
+                let updates = self.piggyback();
+                self.transport.send(&from, Message::Ack { seq, updates });
+            }
+
+            Message::PingReq { seq, target, .. } => {
+                let own_seq = self.next_seq();
+                self.forwards.insert(own_seq, Forward {
+                    origin: from,
+                    seq,
+                    created_at: self.now,
+                });
+
+                let updates = self.piggyback();
+                self.transport.send(&target, Message::Ping {
+                    seq: own_seq,
+                    updates,
+                });
+            }
+
+            Message::Ack { seq, .. } => {
+                if let Some(forward) = self.forwards.remove(&seq) {
+                    let updates = self.piggyback();
+                    self.transport.send(&forward.origin, Message::Ack {
+                        seq: forward.seq,
+                        updates,
+                    });
+                    return;
+                }
+
+                let Some(probe) = &mut self.probe else {
+                    return;
+                };
+
+                if probe.seq == seq && !probe.acked {
+                    probe.acked = true;
+
+                    if let Some(member) = self.members.get(&probe.target) {
+                        member.detector.heartbeat();
+                    }
+                }
+            }
+        }
+    }
+
+    fn start_probe(&mut self) {
+        let Some(target) = self.next_target() else {
+            return;
+        };
+
+        let seq = self.next_seq();
+        let updates = self.piggyback();
+        self.transport.send(&target, Message::Ping { seq, updates });
+
+        self.probe = Some(Probe {
+            target,
+            seq,
+            started_at: self.now,
+            acked: false,
+            indirect: false,
+        });
+    }
+
+    fn probe_indirect(&mut self, probe: &Probe<I>) {
+        let mut candidates: Vec<_> = self
+            .order
+            .iter()
+            .filter(|id| **id != probe.target && self.members[*id].state == MemberState::Alive)
+            .cloned()
+            .collect();
+
+        self.rng.shuffle(&mut candidates);
+        candidates.truncate(self.indirect_probes);
+
+        for id in candidates {
+            let updates = self.piggyback();
+            self.transport.send(&id, Message::PingReq {
+                seq: probe.seq,
+                target: probe.target.clone(),
+                updates,
+            });
+        }
+    }
+
+    /// Returns the next member to probe. Members are probed in random order,
+    /// with each live member being probed once per round.
+    fn next_target(&mut self) -> Option<I> {
+        loop {
+            if self.probe_order.is_empty() {
+                self.probe_order = self
+                    .order
+                    .iter()
+                    .filter(|id| self.members[*id].state != MemberState::Dead)
+                    .cloned()
+                    .collect();
+
+                if self.probe_order.is_empty() {
+                    return None;
+                }
+
+                self.rng.shuffle(&mut self.probe_order);
+            }
+
+            // Safe unwrap, the list is not empty.
+            let id = self.probe_order.pop().unwrap();
+
+            if self.members[&id].state != MemberState::Dead {
+                return Some(id);
+            }
+        }
+    }
+
+    fn next_seq(&mut self) -> u64 {
+        self.seq += 1;
+        self.seq
+    }
+
+    fn suspect(&mut self, id: &I) {
+        let Some(member) = self.members.get(id) else {
+            return;
+        };
+
+        if member.state == MemberState::Alive {
+            self.apply(Update {
+                id: id.clone(),
+                incarnation: member.incarnation,
+                state: MemberState::Suspect,
+            });
+        }
+    }
+
+    fn expire_suspicions(&mut self) {
+        let expired: Vec<_> = self
+            .order
+            .iter()
+            .filter(|id| {
+                let member = &self.members[*id];
+                member.state == MemberState::Suspect
+                    && self.now - member.suspected_at >= self.suspicion_timeout
+            })
+            .map(|id| Update {
+                id: id.clone(),
+                incarnation: self.members[id].incarnation,
+                state: MemberState::Dead,
+            })
+            .collect();
+
+        for update in expired {
+            self.apply(update);
+        }
+    }
+
+    /// Applies the update if it supersedes the current state of the member,
+    /// and disseminates it further.
+    fn apply(&mut self, update: Update<I>) {
+        if update.id == self.id {
+            // Refute suspicions about the local node.
+            if update.state != MemberState::Alive && update.incarnation >= self.incarnation {
+                if let Some(local_health) = &self.local_health {
+                    local_health.record_failure();
+                }
+
+                self.incarnation = update.incarnation + 1;
+                self.announce();
+            }
+
+            return;
+        }
+
+        match self.members.get_mut(&update.id) {
+            Some(member) => {
+                if !supersedes(member, &update) {
+                    return;
+                }
+
+                if update.state == MemberState::Suspect {
+                    member.suspected_at = self.now;
+                }
+
+                member.state = update.state;
+                member.incarnation = update.incarnation;
+            }
+
+            None => {
+                let detector = (self.factory)(&update.id);
+
+                // Start accruing suspicion from the moment the member is
+                // discovered.
+                if update.state == MemberState::Alive {
+                    detector.heartbeat();
+                }
+
+                self.order.push(update.id.clone());
+                self.members.insert(update.id.clone(), Member {
+                    state: update.state,
+                    incarnation: update.incarnation,
+                    suspected_at: self.now,
+                    detector,
+                });
+            }
+        }
+
+        self.broadcast(update);
+    }
+
+    fn announce(&mut self) {
+        self.broadcast(Update {
+            id: self.id.clone(),
+            incarnation: self.incarnation,
+            state: MemberState::Alive,
+        });
+    }
+
+    fn broadcast(&mut self, update: Update<I>) {
+        self.broadcasts
+            .retain(|broadcast| broadcast.update.id != update.id);
+        self.broadcasts.push(Broadcast {
+            update,
+            transmits: 0,
+        });
+    }
+
+    /// Returns the updates to piggyback on an outgoing message, preferring the
+    /// least transmitted ones.
+    fn piggyback(&mut self) -> Vec<Update<I>> {
+        let limit =
+            self.retransmit_mult * ((self.members.len() + 1) as f64).log10().ceil() as usize;
+        let limit = limit.max(self.retransmit_mult);
+
+        self.broadcasts.sort_by_key(|broadcast| broadcast.transmits);
+
+        let updates = self
+            .broadcasts
+            .iter_mut()
+            .take(self.max_piggyback)
+            .map(|broadcast| {
+                broadcast.transmits += 1;
+                broadcast.update.clone()
+            })
+            .collect();
+
+        self.broadcasts
+            .retain(|broadcast| broadcast.transmits < limit);
+
+        updates
+    }
+}
+
+/// Returns `true` if the update overrides the current state of the member.
+fn supersedes<D>(member: &Member<D>, update: &Update<impl Sized>) -> bool {
+    use MemberState::*;
+
+    match (member.state, update.state) {
+        (Dead, Alive) => update.incarnation > member.incarnation,
+        (Dead, _) => false,
+        (_, Alive) => update.incarnation > member.incarnation,
+        (Alive, Suspect) => update.incarnation >= member.incarnation,
+        (Suspect, Suspect) => update.incarnation > member.incarnation,
+        (_, Dead) => true,
+    }
+}
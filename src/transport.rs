@@ -0,0 +1,158 @@
+//! Message transport used by the membership protocols.
+//!
+//! Protocols only need to send messages, and are fed incoming messages by the
+//! caller, so that they can be driven deterministically. [`InMemoryNetwork`]
+//! connects any number of nodes within a single process and allows simulating
+//! network partitions in tests.
+
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    hash::Hash,
+    sync::{Arc, Mutex},
+};
+
+/// Unreliable, unordered message transport addressing nodes by `I`.
+pub trait Transport<I, M> {
+    /// Sends the message to the node. Delivery is not guaranteed and failures
+    /// are not reported.
+    fn send(&self, to: &I, message: M);
+}
+
+/// In-process network delivering messages between [`InMemoryTransport`]
+/// endpoints.
+///
+/// Messages are queued per recipient until received with
+/// [`InMemoryNetwork::recv`]. Messages sent across a partition are dropped.
+pub struct InMemoryNetwork<I, M> {
+    inner: Arc<Mutex<Inner<I, M>>>,
+}
+
+struct Inner<I, M> {
+    queues: HashMap<I, VecDeque<(I, M)>>,
+    disconnected: HashSet<(I, I)>,
+    isolated: HashSet<I>,
+}
+
+impl<I, M> InMemoryNetwork<I, M>
+where
+    I: Eq + Hash + Clone,
+{
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(Inner {
+                queues: HashMap::new(),
+                disconnected: HashSet::new(),
+                isolated: HashSet::new(),
+            })),
+        }
+    }
+
+    /// Returns the transport sending messages on behalf of the node.
+    pub fn endpoint(&self, id: I) -> InMemoryTransport<I, M> {
+        InMemoryTransport {
+            network: self.clone(),
+            id,
+        }
+    }
+
+    /// Returns the next message queued for the node along with its sender.
+    pub fn recv(&self, id: &I) -> Option<(I, M)> {
+        self.inner.lock().unwrap().queues.get_mut(id)?.pop_front()
+    }
+
+    /// Returns the number of messages queued for the node.
+    pub fn pending(&self, id: &I) -> usize {
+        self.inner
+            .lock()
+            .unwrap()
+            .queues
+            .get(id)
+            .map_or(0, VecDeque::len)
+    }
+
+    /// Drops all messages between the two nodes, in both directions.
+    pub fn disconnect(&self, a: &I, b: &I) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.disconnected.insert((a.clone(), b.clone()));
+        inner.disconnected.insert((b.clone(), a.clone()));
+    }
+
+    /// Drops all messages between nodes on different sides of the partition.
+    pub fn partition(&self, side_a: &[I], side_b: &[I]) {
+        for a in side_a {
+            for b in side_b {
+                self.disconnect(a, b);
+            }
+        }
+    }
+
+    /// Drops all messages to and from the node, and discards its queue.
+    pub fn isolate(&self, id: &I) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.queues.remove(id);
+        inner.isolated.insert(id.clone());
+    }
+
+    /// Restores connectivity between all nodes.
+    pub fn heal(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.disconnected.clear();
+        inner.isolated.clear();
+    }
+
+    fn send(&self, from: &I, to: &I, message: M) {
+        let mut inner = self.inner.lock().unwrap();
+
+        if inner.isolated.contains(from)
+            || inner.isolated.contains(to)
+            || inner.disconnected.contains(&(from.clone(), to.clone()))
+        {
+            return;
+        }
+
+        inner
+            .queues
+            .entry(to.clone())
+            .or_default()
+            .push_back((from.clone(), message));
+    }
+}
+
+impl<I, M> Clone for InMemoryNetwork<I, M> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<I, M> Default for InMemoryNetwork<I, M>
+where
+    I: Eq + Hash + Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Endpoint of an [`InMemoryNetwork`].
+pub struct InMemoryTransport<I, M> {
+    network: InMemoryNetwork<I, M>,
+    id: I,
+}
+
+impl<I, M> InMemoryTransport<I, M> {
+    /// Returns the node this endpoint sends messages on behalf of.
+    pub fn id(&self) -> &I {
+        &self.id
+    }
+}
+
+impl<I, M> Transport<I, M> for InMemoryTransport<I, M>
+where
+    I: Eq + Hash + Clone,
+{
+    fn send(&self, to: &I, message: M) {
+        self.network.send(&self.id, to, message);
+    }
+}
@@ -0,0 +1,109 @@
+use {
+    crate::{Clock, DefaultClock, LocalHealth},
+    std::time::Duration,
+};
+
+/// Detector of local stalls, e.g. allocator pauses, VM steal time or the
+/// process being stopped.
+///
+/// A stalled process sees huge elapsed times for every monitored resource at
+/// once, and would suspect all of them. The watchdog is meant to be checked
+/// periodically, every `interval`, from a timer or a dedicated thread, and
+/// detects a stall when the time elapsed since the previous check exceeds the
+/// interval by more than the tolerance. The stalled time can then be excluded
+/// from the detectors using
+/// [`Detector::exclude_stall`](crate::Detector::exclude_stall) or
+/// [`Registry::exclude_stall`](crate::Registry::exclude_stall):
+///
+/// ```
+/// use {
+///     phi_accrual_failure_detector::{Registry, SyncDetector, Watchdog},
+///     std::time::Duration,
+/// };
+///
+/// let registry = Registry::<&str, _>::new(|_| SyncDetector::default());
+/// let mut watchdog = Watchdog::new(Duration::from_millis(100));
+///
+/// // Called every 100ms.
+/// if let Some(stall) = watchdog.check() {
+///     registry.exclude_stall(stall);
+/// }
+/// ```
+pub struct Watchdog<C: Clock = DefaultClock> {
+    clock: C,
+    interval: Duration,
+    tolerance: Duration,
+    local_health: Option<LocalHealth>,
+    last_check: Option<C::Timestamp>,
+}
+
+impl Watchdog {
+    /// Creates a watchdog expecting to be checked every `interval`.
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            clock: DefaultClock,
+            interval,
+            tolerance: interval,
+            local_health: None,
+            last_check: None,
+        }
+    }
+}
+
+impl<C: Clock> Watchdog<C> {
+    /// Delay of a check beyond `interval` that is not considered a stall.
+    ///
+    /// Default: same as `interval`
+    pub fn tolerance(mut self, tolerance: Duration) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Local health awareness to record detected stalls into as failures, see
+    /// [`LocalHealth`].
+    ///
+    /// Default: None
+    pub fn local_health(mut self, local_health: LocalHealth) -> Self {
+        self.local_health = Some(local_health);
+        self
+    }
+
+    /// Provide an alternative implementation of [`Clock`]. Should be the same
+    /// clock the detectors are using.
+    ///
+    /// Default: [`DefaultClock`]
+    pub fn clock<T: Clock>(self, clock: T) -> Watchdog<T> {
+        Watchdog {
+            clock,
+            interval: self.interval,
+            tolerance: self.tolerance,
+            local_health: self.local_health,
+            last_check: None,
+        }
+    }
+
+    /// Checks for a stall since the previous check, returning its duration,
+    /// i.e. the time elapsed beyond `interval`. The first check never detects
+    /// a stall.
+    pub fn check(&mut self) -> Option<Duration> {
+        let timestamp = self.clock.timestamp();
+        let last_check = self.last_check.replace(timestamp);
+        let elapsed = C::elapsed(last_check.as_ref()?, self.last_check.as_ref()?);
+
+        if elapsed <= self.interval + self.tolerance {
+            return None;
+        }
+
+        #[cfg(feature = "tracing")]
+        tracing::warn!(
+            stall_ms = (elapsed - self.interval).as_millis() as u64,
+            "local stall detected"
+        );
+
+        if let Some(local_health) = &self.local_health {
+            local_health.record_failure();
+        }
+
+        Some(elapsed - self.interval)
+    }
+}
@@ -0,0 +1,296 @@
+//! Compact binary wire format for heartbeat messages.
+//!
+//! All integers are encoded in network (big-endian) byte order:
+//!
+//! | Offset | Size | Field                                                   |
+//! |--------|------|---------------------------------------------------------|
+//! | 0      | 2    | Magic, `0x50 0x48` (`"PH"`)                             |
+//! | 2      | 1    | Version, currently `1`                                  |
+//! | 3      | 1    | Flags, reserved and must be ignored by decoders         |
+//! | 4      | 8    | Node ID                                                 |
+//! | 12     | 8    | Incarnation, changes when the node restarts             |
+//! | 20     | 8    | Sequence number, incremented with each heartbeat        |
+//! | 28     | 8    | Sender timestamp, milliseconds since UNIX epoch         |
+//! | 36     | 2    | Payload length `N`                                      |
+//! | 38     | `N`  | Payload, opaque to the detector                         |
+//!
+//! A frame must span the whole buffer it's decoded from, i.e. trailing bytes
+//! are rejected.
+
+/// Frame magic bytes.
+pub const MAGIC: [u8; 2] = *b"PH";
+
+/// Current version of the wire format.
+pub const VERSION: u8 = 1;
+
+/// Length of the frame header, i.e. of a frame without payload.
+pub const HEADER_LEN: usize = 38;
+
+/// Maximum length of the payload.
+pub const MAX_PAYLOAD_LEN: usize = u16::MAX as usize;
+
+/// Maximum length of an encoded frame.
+pub const MAX_FRAME_LEN: usize = HEADER_LEN + MAX_PAYLOAD_LEN;
+
+/// Errors encoding or decoding a [`Frame`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum FrameError {
+    #[error("Frame is truncated: expected {expected} bytes, got {actual}")]
+    Truncated { expected: usize, actual: usize },
+
+    #[error("Frame has {0} trailing bytes")]
+    TrailingBytes(usize),
+
+    #[error("Invalid frame magic")]
+    InvalidMagic,
+
+    #[error("Unsupported frame version: {0}")]
+    UnsupportedVersion(u8),
+
+    #[error("Payload is too large: {0} bytes")]
+    PayloadTooLarge(usize),
+
+    #[error("Buffer is too small: {required} bytes required, {available} available")]
+    BufferTooSmall { required: usize, available: usize },
+}
+
+/// Heartbeat frame. The payload is borrowed from the buffer the frame was
+/// decoded from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Frame<'a> {
+    /// ID of the sending node.
+    pub node_id: u64,
+
+    /// Incarnation of the sending node, which changes when the node restarts.
+    pub incarnation: u64,
+
+    /// Sequence number of the heartbeat, incremented with each heartbeat sent
+    /// within an incarnation.
+    pub sequence: u64,
+
+    /// Time the heartbeat was sent at, in milliseconds since UNIX epoch,
+    /// according to the sender clock.
+    pub timestamp: u64,
+
+    /// Optional piggybacked payload.
+    pub payload: &'a [u8],
+}
+
+impl<'a> Frame<'a> {
+    /// Length of the encoded frame.
+    pub fn encoded_len(&self) -> usize {
+        HEADER_LEN + self.payload.len()
+    }
+
+    /// Encodes the frame into the beginning of `buf`, returning the number of
+    /// bytes written.
+    pub fn encode(&self, buf: &mut [u8]) -> Result<usize, FrameError> {
+        if self.payload.len() > MAX_PAYLOAD_LEN {
+            return Err(FrameError::PayloadTooLarge(self.payload.len()));
+        }
+
+        let len = self.encoded_len();
+
+        if buf.len() < len {
+            return Err(FrameError::BufferTooSmall {
+                required: len,
+                available: buf.len(),
+            });
+        }
+
+        buf[0..2].copy_from_slice(&MAGIC);
+        buf[2] = VERSION;
+        buf[3] = 0;
+        buf[4..12].copy_from_slice(&self.node_id.to_be_bytes());
+        buf[12..20].copy_from_slice(&self.incarnation.to_be_bytes());
+        buf[20..28].copy_from_slice(&self.sequence.to_be_bytes());
+        buf[28..36].copy_from_slice(&self.timestamp.to_be_bytes());
+        buf[36..38].copy_from_slice(&(self.payload.len() as u16).to_be_bytes());
+        buf[HEADER_LEN..len].copy_from_slice(self.payload);
+
+        Ok(len)
+    }
+
+    /// Encodes the frame into a new [`Vec`].
+    pub fn to_vec(&self) -> Result<Vec<u8>, FrameError> {
+        let mut buf = vec![0; self.encoded_len()];
+        self.encode(&mut buf)?;
+        Ok(buf)
+    }
+
+    /// Decodes a frame spanning the whole `buf`.
+    pub fn decode(buf: &'a [u8]) -> Result<Self, FrameError> {
+        if buf.len() < HEADER_LEN {
+            return Err(FrameError::Truncated {
+                expected: HEADER_LEN,
+                actual: buf.len(),
+            });
+        }
+
+        if buf[0..2] != MAGIC {
+            return Err(FrameError::InvalidMagic);
+        }
+
+        if buf[2] != VERSION {
+            return Err(FrameError::UnsupportedVersion(buf[2]));
+        }
+
+        let u64_at =
+            |offset: usize| u64::from_be_bytes(buf[offset..offset + 8].try_into().unwrap());
+        let payload_len = u16::from_be_bytes([buf[36], buf[37]]) as usize;
+        let len = HEADER_LEN + payload_len;
+
+        if buf.len() < len {
+            return Err(FrameError::Truncated {
+                expected: len,
+                actual: buf.len(),
+            });
+        }
+
+        if buf.len() > len {
+            return Err(FrameError::TrailingBytes(buf.len() - len));
+        }
+
+        Ok(Self {
+            node_id: u64_at(4),
+            incarnation: u64_at(12),
+            sequence: u64_at(20),
+            timestamp: u64_at(28),
+            payload: &buf[HEADER_LEN..],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::rng::Rng};
+
+    fn random_frame<'a>(rng: &mut Rng, payload: &'a mut Vec<u8>) -> Frame<'a> {
+        payload.clear();
+        payload.extend((0..rng.next_u64() % 64).map(|_| rng.next_u64() as u8));
+
+        Frame {
+            node_id: rng.next_u64(),
+            incarnation: rng.next_u64(),
+            sequence: rng.next_u64(),
+            timestamp: rng.next_u64(),
+            payload,
+        }
+    }
+
+    #[test]
+    fn encoding() {
+        let frame = Frame {
+            node_id: 1,
+            incarnation: 2,
+            sequence: 3,
+            timestamp: 4,
+            payload: b"hi",
+        };
+
+        let buf = frame.to_vec().unwrap();
+        assert_eq!(buf.len(), 40);
+        assert_eq!(&buf[..4], b"PH\x01\x00");
+        assert_eq!(buf[11], 1);
+        assert_eq!(buf[19], 2);
+        assert_eq!(buf[27], 3);
+        assert_eq!(buf[35], 4);
+        assert_eq!(&buf[36..], b"\x00\x02hi");
+        assert_eq!(Frame::decode(&buf), Ok(frame));
+
+        assert_eq!(
+            frame.encode(&mut [0; 39]),
+            Err(FrameError::BufferTooSmall {
+                required: 40,
+                available: 39
+            })
+        );
+    }
+
+    #[test]
+    fn decoding_errors() {
+        let buf = Frame {
+            node_id: 1,
+            incarnation: 2,
+            sequence: 3,
+            timestamp: 4,
+            payload: b"hi",
+        }
+        .to_vec()
+        .unwrap();
+
+        assert_eq!(
+            Frame::decode(&buf[..39]),
+            Err(FrameError::Truncated {
+                expected: 40,
+                actual: 39
+            })
+        );
+        assert_eq!(
+            Frame::decode(&buf[..10]),
+            Err(FrameError::Truncated {
+                expected: HEADER_LEN,
+                actual: 10
+            })
+        );
+        assert_eq!(
+            Frame::decode(&[&buf[..], &[0]].concat()),
+            Err(FrameError::TrailingBytes(1))
+        );
+
+        let mut invalid = buf.clone();
+        invalid[0] = b'X';
+        assert_eq!(Frame::decode(&invalid), Err(FrameError::InvalidMagic));
+
+        let mut invalid = buf.clone();
+        invalid[2] = 2;
+        assert_eq!(
+            Frame::decode(&invalid),
+            Err(FrameError::UnsupportedVersion(2))
+        );
+    }
+
+    #[test]
+    fn fuzz_roundtrip() {
+        let mut rng = Rng::new(0);
+        let mut payload = Vec::new();
+
+        for _ in 0..10_000 {
+            let frame = random_frame(&mut rng, &mut payload);
+            let buf = frame.to_vec().unwrap();
+            assert_eq!(Frame::decode(&buf), Ok(frame));
+        }
+    }
+
+    #[test]
+    fn fuzz_decode() {
+        let mut rng = Rng::new(1);
+        let mut payload = Vec::new();
+
+        for _ in 0..10_000 {
+            // Random bytes, mostly with a valid prefix to get past the header checks.
+            let len = (rng.next_u64() % 128) as usize;
+            let mut buf: Vec<_> = (0..len).map(|_| rng.next_u64() as u8).collect();
+
+            if buf.len() >= 3 && !rng.next_u64().is_multiple_of(4) {
+                buf[..2].copy_from_slice(&MAGIC);
+                buf[2] = VERSION;
+            }
+
+            if let Ok(frame) = Frame::decode(&buf) {
+                assert_eq!(frame.encoded_len(), buf.len());
+                assert_eq!(frame.to_vec().unwrap()[4..], buf[4..]);
+            }
+
+            // Valid frames with random bit flips and truncation.
+            let mut buf = random_frame(&mut rng, &mut payload).to_vec().unwrap();
+            let idx = rng.next_u64() as usize % buf.len();
+            buf[idx] ^= 1 << (rng.next_u64() % 8);
+            buf.truncate(buf.len() - rng.next_u64() as usize % 4);
+
+            if let Ok(frame) = Frame::decode(&buf) {
+                assert_eq!(frame.encoded_len(), buf.len());
+            }
+        }
+    }
+}
@@ -0,0 +1,121 @@
+#![cfg(feature = "cli")]
+
+use std::{fs, path::PathBuf, process::Command};
+
+fn replay(name: &str, contents: &str, args: &[&str]) -> (bool, String, String) {
+    run("replay", name, contents, args)
+}
+
+fn run(command: &str, name: &str, contents: &str, args: &[&str]) -> (bool, String, String) {
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    fs::write(&path, contents).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_phi-replay"))
+        .arg(command)
+        .arg(&path)
+        .args(args)
+        .output()
+        .unwrap();
+
+    (
+        output.status.success(),
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+fn log(peers: &[(&str, &[u64])]) -> Vec<(String, u64)> {
+    let mut arrivals: Vec<_> = peers
+        .iter()
+        .flat_map(|(peer, times)| times.iter().map(|time| (peer.to_string(), *time)))
+        .collect();
+
+    arrivals.sort_by_key(|(_, time)| *time);
+    arrivals
+}
+
+const A: &[u64] = &[0, 1000, 2000, 3000, 4000, 5000, 6000, 20_000];
+const B: &[u64] = &[0, 1000, 2000, 3000];
+
+#[test]
+fn csv_summary() {
+    let csv: String = std::iter::once("peer,timestamp_ms\n".to_owned())
+        .chain(
+            log(&[("a", A), ("b", B)])
+                .into_iter()
+                .map(|(peer, time)| format!("{peer},{time}\n")),
+        )
+        .collect();
+
+    let (success, stdout, _) = replay("log.csv", &csv, &["--acceptable-heartbeat-pause-ms", "0"]);
+    assert!(success);
+
+    let lines: Vec<_> = stdout.lines().collect();
+    assert!(lines[0].starts_with("a: 8 heartbeats, 1 suspicions, 1 false positives ("));
+    assert!(lines[1].ends_with("recovered at 20.000s (false positive)"));
+    assert!(lines[2].starts_with("b: 4 heartbeats, 1 suspicions, 0 false positives"));
+    assert!(lines[3].ends_with("(detection)"));
+    assert!(lines.contains(&"false positives: 1"));
+    assert!(lines.contains(&"detections: 1"));
+}
+
+#[test]
+fn jsonl_phi() {
+    let jsonl: String = log(&[("a", A)])
+        .into_iter()
+        .map(|(peer, time)| format!("{{\"peer\": \"{peer}\", \"timestamp_ms\": {time}}}\n"))
+        .collect();
+
+    let (success, stdout, _) = replay("log.jsonl", &jsonl, &["--phi-interval-ms", "1000"]);
+    assert!(success);
+
+    let lines: Vec<_> = stdout.lines().collect();
+    assert_eq!(lines[0], "peer,timestamp_ms,phi");
+    assert_eq!(lines.len(), 22);
+    assert_eq!(lines[1], "a,0,0.000");
+    assert!(lines[21].starts_with("a,20000,"));
+}
+
+#[test]
+fn invalid_input() {
+    let (success, _, stderr) = replay("invalid.csv", "a,0\na,x\n", &[]);
+    assert!(!success);
+    assert_eq!(stderr, "error: line 2: invalid timestamp `x`\n");
+
+    let (success, _, stderr) = replay("invalid.jsonl", "{\"peer\": \"a\"}\n", &[]);
+    assert!(!success);
+    assert!(stderr.starts_with("error: line 1: missing field `timestamp_ms`"));
+
+    let (success, _, stderr) = replay("empty.csv", "a,0\n", &["--threshold", "0"]);
+    assert!(!success);
+    assert_eq!(stderr, "error: Threshold must be > 0\n");
+}
+
+#[test]
+fn sweep_recommendation() {
+    let csv: String = (0..600)
+        .flat_map(|i| ["a", "b"].map(|peer| format!("{peer},{}\n", i * 1000)))
+        .collect();
+
+    let (success, stdout, _) = run("sweep", "sweep.csv", &csv, &[
+        "--thresholds",
+        "1,8",
+        "--pauses-ms",
+        "0,3000",
+        "--sample-sizes",
+        "100",
+        "--crash-at-ms",
+        "300000",
+    ]);
+    assert!(success);
+
+    let lines: Vec<_> = stdout.lines().collect();
+    assert!(lines[0].starts_with("threshold,acceptable_heartbeat_pause_ms,"));
+
+    let recommended = lines
+        .iter()
+        .position(|line| *line == "recommended:")
+        .unwrap();
+    assert!(recommended > 1);
+    assert!(lines[recommended + 1].ends_with(",0,0,0.000"));
+}
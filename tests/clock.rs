@@ -0,0 +1,247 @@
+use {
+    phi_accrual_failure_detector::*,
+    std::{
+        sync::Arc,
+        thread,
+        time::{Duration, Instant},
+    },
+};
+
+#[test]
+fn manual_clock() {
+    let clock = ManualClock::new();
+    assert_eq!(clock.timestamp(), Duration::ZERO);
+
+    clock.advance(Duration::from_millis(1500));
+    assert_eq!(clock.now(), Duration::from_millis(1500));
+
+    let before = clock.timestamp();
+    clock.set(Duration::from_secs(10));
+    let after = clock.timestamp();
+
+    assert_eq!(
+        ManualClock::elapsed(&before, &after),
+        Duration::from_millis(8500)
+    );
+    assert_eq!(ManualClock::elapsed(&after, &before), Duration::ZERO);
+}
+
+#[test]
+fn manual_clock_shared() {
+    let clock = ManualClock::new();
+    let other = clock.clone();
+
+    other.advance(Duration::from_secs(1));
+    assert_eq!(clock.now(), Duration::from_secs(1));
+
+    clock.set(Duration::from_secs(5));
+    assert_eq!(other.now(), Duration::from_secs(5));
+}
+
+#[test]
+fn manual_clock_lockstep() {
+    let clock = ManualClock::new();
+    let detectors: Vec<_> = (0..4)
+        .map(|_| {
+            Arc::new(
+                SyncDetector::builder()
+                    .clock(clock.clone())
+                    .build()
+                    .unwrap(),
+            )
+        })
+        .collect();
+
+    for _ in 0..10 {
+        thread::scope(|scope| {
+            for detector in &detectors {
+                scope.spawn(|| detector.heartbeat());
+            }
+        });
+
+        clock.advance(Duration::from_secs(1));
+    }
+
+    assert!(detectors.iter().all(|detector| detector.is_available()));
+    assert!(detectors
+        .iter()
+        .all(|detector| detector.stats().last_interval == Some(Duration::from_secs(1))));
+
+    clock.advance(Duration::from_secs(5));
+    assert!(detectors.iter().all(|detector| !detector.is_available()));
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test(start_paused = true)]
+async fn tokio_clock_paused() {
+    let detector = UnsyncDetector::builder().clock(TokioClock).build().unwrap();
+
+    for _ in 0..10 {
+        detector.heartbeat();
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+
+    let stats = detector.stats();
+    assert!(detector.is_available());
+    assert_eq!(stats.last_interval, Some(Duration::from_secs(1)));
+    assert_eq!(
+        stats.time_since_last_heartbeat,
+        Some(Duration::from_secs(1))
+    );
+
+    tokio::time::advance(Duration::from_secs(10)).await;
+    assert!(!detector.is_available());
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test(start_paused = true)]
+async fn tokio_clock_scheduler() {
+    let detector = Arc::new(SyncDetector::builder().clock(TokioClock).build().unwrap());
+
+    let task = HeartbeatScheduler::new(Duration::from_secs(1))
+        .clock(TokioClock)
+        .spawn({
+            let detector = detector.clone();
+            move |_| detector.heartbeat()
+        });
+
+    tokio::time::sleep(Duration::from_millis(10_500)).await;
+    task.abort();
+
+    // Heartbeats are sent exactly on schedule in virtual time.
+    let stats = detector.stats();
+    assert_eq!(stats.heartbeat_count, 11);
+    assert_eq!(stats.last_interval, Some(Duration::from_secs(1)));
+}
+
+#[test]
+fn coarse_clock() {
+    let clock = CoarseClock::new(Duration::from_millis(5));
+    assert_eq!(clock.resolution(), Duration::from_millis(5));
+
+    let before = clock.timestamp();
+    let deadline = Instant::now() + Duration::from_secs(5);
+
+    while clock.timestamp() == before {
+        assert!(Instant::now() < deadline, "the ticker thread never ticked");
+        thread::sleep(Duration::from_millis(5));
+    }
+
+    // Timestamps lag behind real time, but never run ahead of it.
+    let after = clock.timestamp();
+    assert!(after <= Instant::now());
+    assert_eq!(CoarseClock::elapsed(&after, &before), Duration::ZERO);
+}
+
+#[test]
+fn coarse_clock_lag() {
+    let resolution = Duration::from_millis(5);
+    let source = ManualClock::new();
+    let clock = CoarseClock::manual(resolution, source.clone());
+    let start = clock.timestamp();
+
+    for ms in 1..=100 {
+        source.advance(Duration::from_millis(1));
+
+        if ms % 5 == 0 {
+            clock.tick();
+        }
+
+        let lag = source.now() - CoarseClock::elapsed(&start, &clock.timestamp());
+        assert!(lag < resolution, "{lag:?}");
+    }
+
+    // Without ticks the clock stands still.
+    source.advance(Duration::from_secs(1));
+    assert_eq!(
+        CoarseClock::elapsed(&start, &clock.timestamp()),
+        Duration::from_millis(100)
+    );
+}
+
+#[test]
+fn coarse_clock_detection_accuracy() {
+    let config = Config {
+        min_std_deviation: Duration::from_millis(10),
+        acceptable_heartbeat_pause: Duration::from_millis(100),
+        first_heartbeat_estimate: Duration::from_millis(20),
+        ..Default::default()
+    };
+
+    let resolution = Duration::from_millis(5);
+    let source = ManualClock::new();
+    let coarse_clock = CoarseClock::manual(resolution, source.clone());
+    let precise = UnsyncDetector::builder()
+        .config(config.clone())
+        .clock(source.clone())
+        .build()
+        .unwrap();
+    let coarse = UnsyncDetector::builder()
+        .config(config)
+        .clock(coarse_clock.clone())
+        .build()
+        .unwrap();
+
+    // Ticks every 5ms, heartbeats every 20ms, 3ms after a tick, then silence.
+    let (mut precise_detection, mut coarse_detection) = (None, None);
+
+    for ms in 0..5_000u64 {
+        if ms % 5 == 0 {
+            coarse_clock.tick();
+        }
+
+        if ms < 400 && ms % 20 == 3 {
+            precise.heartbeat();
+            coarse.heartbeat();
+        }
+
+        if precise_detection.is_none() && !precise.is_available() {
+            precise_detection = Some(source.now());
+        }
+
+        if coarse_detection.is_none() && !coarse.is_available() {
+            coarse_detection = Some(source.now());
+        }
+
+        source.advance(Duration::from_millis(1));
+    }
+
+    fn mean(detector: &impl Detector) -> Duration {
+        detector.stats().mean
+    }
+
+    assert_eq!(mean(&precise), mean(&coarse));
+
+    // Detection differs by up to the resolution.
+    let precise_detection = precise_detection.unwrap();
+    let coarse_detection = coarse_detection.unwrap();
+    assert!(
+        coarse_detection.abs_diff(precise_detection) <= resolution,
+        "{coarse_detection:?} {precise_detection:?}"
+    );
+}
+
+#[test]
+fn system_clock() {
+    let clock = SystemClock::new().max_forward_jump(Duration::from_millis(500));
+    let detector = UnsyncDetector::builder()
+        .clock(clock.clone())
+        .build()
+        .unwrap();
+
+    detector.heartbeat();
+    thread::sleep(Duration::from_millis(20));
+    detector.heartbeat();
+
+    let stats = detector.stats();
+    assert!(stats.last_interval.unwrap() >= Duration::from_millis(20));
+    assert!(detector.is_available());
+    assert_eq!(clock.jumps(), 0);
+    assert_eq!(clock.excluded(), Duration::ZERO);
+
+    let before = clock.timestamp();
+    assert_eq!(
+        SystemClock::elapsed(&before, &(before - Duration::from_secs(1))),
+        Duration::ZERO
+    );
+}
@@ -0,0 +1,175 @@
+use {
+    phi_accrual_failure_detector::{downing::*, ManualClock},
+    std::{collections::BTreeSet, time::Duration},
+};
+
+/// Members `0..size`, with member `size - 1` being the oldest, so that age and
+/// identifier order differ.
+fn members(size: u64) -> Vec<Member<u64>> {
+    (0..size)
+        .map(|id| Member {
+            id,
+            up_number: size - id,
+        })
+        .collect()
+}
+
+/// Splits the members into two non-empty sides for every possible partition.
+fn partitions(
+    members: &[Member<u64>],
+) -> impl Iterator<Item = (BTreeSet<u64>, BTreeSet<u64>)> + '_ {
+    (1..(1u64 << members.len()) - 1).map(|mask| {
+        members
+            .iter()
+            .map(|member| member.id)
+            .partition(|id| mask & (1 << id) != 0)
+    })
+}
+
+/// Returns whether each side keeps running, according to its own decision.
+fn survivors(
+    strategy: &Strategy<u64>,
+    members: &[Member<u64>],
+    side_a: &BTreeSet<u64>,
+    side_b: &BTreeSet<u64>,
+) -> (bool, bool) {
+    let downing_a = strategy.resolve(members, side_b);
+    let downing_b = strategy.resolve(members, side_a);
+
+    // Each side downs exactly the members of one or both sides.
+    for (downing, own, other) in [(&downing_a, side_a, side_b), (&downing_b, side_b, side_a)] {
+        let expected: Vec<_> = match downing.decision {
+            Decision::DownUnreachable => other.iter().copied().collect(),
+            Decision::DownReachable => own.iter().copied().collect(),
+            Decision::DownAll => (0..members.len() as u64).collect(),
+        };
+        assert_eq!(downing.down, expected);
+    }
+
+    let survivors = (
+        !downing_a.decision.downs_self(),
+        !downing_b.decision.downs_self(),
+    );
+
+    // Never both sides keep running.
+    assert_ne!(survivors, (true, true));
+    survivors
+}
+
+#[test]
+fn partition_scenarios() {
+    for size in 2..=7 {
+        let members = members(size);
+        let oldest = size - 1;
+
+        for (side_a, side_b) in partitions(&members) {
+            let side_of = |id: u64| side_a.contains(&id);
+            let pick = |a: bool| (a, !a);
+
+            let survivors = |strategy| survivors(&strategy, &members, &side_a, &side_b);
+
+            // Keep majority.
+            let expected = if side_a.len() == side_b.len() {
+                pick(side_of(0))
+            } else {
+                pick(side_a.len() > side_b.len())
+            };
+            assert_eq!(survivors(Strategy::KeepMajority), expected);
+
+            // Keep oldest.
+            assert_eq!(
+                survivors(Strategy::KeepOldest {
+                    down_if_alone: false
+                }),
+                pick(side_of(oldest))
+            );
+
+            let oldest_alone = if side_of(oldest) {
+                side_a.len() == 1
+            } else {
+                side_b.len() == 1
+            };
+            assert_eq!(
+                survivors(Strategy::KeepOldest {
+                    down_if_alone: true
+                }),
+                pick(side_of(oldest) != oldest_alone)
+            );
+
+            // Static quorum.
+            for quorum_size in 1..=4 {
+                let expected = if size as usize >= quorum_size * 2 {
+                    (false, false)
+                } else {
+                    (side_a.len() >= quorum_size, side_b.len() >= quorum_size)
+                };
+                assert_eq!(survivors(Strategy::StaticQuorum { quorum_size }), expected);
+            }
+
+            // Keep referee.
+            let referee_side = if side_of(1) { &side_a } else { &side_b };
+            let expected = if referee_side.len() < 2 {
+                (false, false)
+            } else {
+                pick(side_of(1))
+            };
+            assert_eq!(
+                survivors(Strategy::KeepReferee {
+                    referee: 1,
+                    down_all_if_less_than: 2,
+                }),
+                expected
+            );
+
+            // Down all.
+            assert_eq!(survivors(Strategy::DownAll), (false, false));
+        }
+    }
+}
+
+#[test]
+fn unknown_members_ignored() {
+    let members = members(3);
+    let unreachable = BTreeSet::from([2, 7]);
+    let downing = Strategy::KeepMajority.resolve(&members, &unreachable);
+
+    assert_eq!(downing.decision, Decision::DownUnreachable);
+    assert_eq!(downing.down, [2]);
+}
+
+#[test]
+fn stable_after() {
+    let clock = ManualClock::new();
+    let members = members(5);
+    let mut resolver = SplitBrainResolver::new(Strategy::KeepMajority, Duration::from_secs(10))
+        .clock(clock.clone());
+
+    // Nothing to decide while everyone is reachable.
+    assert_eq!(resolver.update(&members, []), None);
+    clock.advance(Duration::from_secs(20));
+    assert_eq!(resolver.update(&members, []), None);
+
+    assert_eq!(resolver.update(&members, [4]), None);
+    clock.advance(Duration::from_secs(6));
+    assert_eq!(resolver.update(&members, [4]), None);
+
+    // A change of the unreachable members restarts the period.
+    assert_eq!(resolver.update(&members, [3, 4]), None);
+    clock.advance(Duration::from_secs(6));
+    assert_eq!(resolver.update(&members, [4, 3]), None);
+    clock.advance(Duration::from_secs(4));
+
+    let downing = resolver.update(&members, [3, 4]).unwrap();
+    assert_eq!(downing.decision, Decision::DownUnreachable);
+    assert_eq!(downing.down, [3, 4]);
+
+    // The decision is made once per stable view.
+    clock.advance(Duration::from_secs(10));
+    assert_eq!(resolver.update(&members, [3, 4]), None);
+
+    assert_eq!(resolver.update(&members, [1, 2, 3, 4]), None);
+    clock.advance(Duration::from_secs(10));
+    let downing = resolver.update(&members, [1, 2, 3, 4]).unwrap();
+    assert_eq!(downing.decision, Decision::DownReachable);
+    assert_eq!(downing.down, [0]);
+}
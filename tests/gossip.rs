@@ -0,0 +1,206 @@
+use {
+    phi_accrual_failure_detector::{gossip::*, transport::*, *},
+    std::{sync::Arc, time::Duration},
+};
+
+type Node =
+    Gossiper<u64, FailureDetector<SyncState<ManualClock>>, InMemoryTransport<u64, Message<u64>>>;
+
+struct Cluster {
+    clock: ManualClock,
+    network: InMemoryNetwork<u64, Message<u64>>,
+    nodes: Vec<Node>,
+    crashed: Vec<u64>,
+}
+
+impl Cluster {
+    /// Creates a cluster of `size` nodes, all joining through the first one.
+    fn new(size: u64) -> Self {
+        let clock = ManualClock::new();
+        let network = InMemoryNetwork::new();
+
+        let nodes = (0..size)
+            .map(|id| {
+                let clock = clock.clone();
+                let registry = Arc::new(Registry::new(move |_| {
+                    SyncDetector::builder()
+                        .acceptable_heartbeat_pause(Duration::from_secs(3))
+                        .clock(clock.clone())
+                        .build()
+                        .unwrap()
+                }));
+
+                let mut node = Gossiper::new(id, network.endpoint(id), registry)
+                    .generation(1)
+                    .seed(id);
+
+                if id != 0 {
+                    node.join(0);
+                }
+
+                node
+            })
+            .collect();
+
+        Self {
+            clock,
+            network,
+            nodes,
+            crashed: Vec::new(),
+        }
+    }
+
+    fn crash(&mut self, id: u64) {
+        self.network.isolate(&id);
+        self.crashed.push(id);
+    }
+
+    /// Runs gossip rounds, one per second.
+    fn run(&mut self, rounds: usize) {
+        for _ in 0..rounds {
+            self.clock.advance(Duration::from_secs(1));
+
+            for node in &mut self.nodes {
+                if !self.crashed.contains(node.id()) {
+                    node.tick();
+                }
+            }
+
+            // Deliver until all exchanges complete.
+            let mut delivered = true;
+            while delivered {
+                delivered = false;
+
+                for node in &mut self.nodes {
+                    while let Some((from, message)) = self.network.recv(node.id()) {
+                        node.handle(from, message);
+                        delivered = true;
+                    }
+                }
+            }
+        }
+    }
+
+    fn live_nodes(&self) -> impl Iterator<Item = &Node> {
+        self.nodes
+            .iter()
+            .filter(|node| !self.crashed.contains(node.id()))
+    }
+}
+
+#[test]
+fn heartbeats_disseminated() {
+    let mut cluster = Cluster::new(8);
+    cluster.run(30);
+
+    for node in &cluster.nodes {
+        let registry = node.registry();
+        assert_eq!(registry.len(), 7);
+
+        for peer in &cluster.nodes {
+            if peer.id() == node.id() {
+                continue;
+            }
+
+            assert!(registry.is_available(peer.id()));
+
+            // Versions propagate within a few rounds.
+            let state = node.peer_state(peer.id()).unwrap();
+            assert!(peer.state().version - state.version <= 5);
+        }
+    }
+}
+
+#[test]
+fn crashed_node_suspected() {
+    let mut cluster = Cluster::new(8);
+    cluster.run(30);
+    cluster.crash(5);
+    cluster.run(30);
+
+    for node in cluster.live_nodes() {
+        let registry = node.registry();
+        assert!(!registry.is_available(&5));
+
+        for peer in cluster.live_nodes() {
+            if peer.id() != node.id() {
+                assert!(registry.is_available(peer.id()));
+            }
+        }
+    }
+}
+
+#[test]
+fn restarted_node_recovers() {
+    let mut cluster = Cluster::new(4);
+    cluster.run(20);
+    cluster.crash(3);
+    cluster.run(30);
+    assert!(!cluster.nodes[0].registry().is_available(&3));
+
+    // The node restarts with a new generation and its version reset.
+    cluster.nodes[3] = Gossiper::new(3, cluster.network.endpoint(3), {
+        let clock = cluster.clock.clone();
+        Arc::new(Registry::new(move |_| {
+            SyncDetector::builder()
+                .clock(clock.clone())
+                .build()
+                .unwrap()
+        }))
+    })
+    .generation(2)
+    .seed(3);
+    cluster.nodes[3].join(0);
+    cluster.crashed.clear();
+    cluster.network.heal();
+    cluster.run(10);
+
+    for node in &cluster.nodes[..3] {
+        let state = node.peer_state(&3).unwrap();
+        assert_eq!(state.generation, 2);
+        assert!(node.registry().is_available(&3));
+    }
+}
+
+#[test]
+fn stale_states_ignored() {
+    let network = InMemoryNetwork::new();
+    let clock = ManualClock::new();
+    let registry = Arc::new(Registry::new({
+        let clock = clock.clone();
+        move |_| {
+            SyncDetector::builder()
+                .clock(clock.clone())
+                .build()
+                .unwrap()
+        }
+    }));
+    let mut node = Gossiper::new(0, network.endpoint(0), registry.clone());
+
+    let ack2 = |generation, version| Message::Ack2 {
+        states: vec![Digest {
+            id: 1,
+            state: HeartbeatState {
+                generation,
+                version,
+            },
+        }],
+    };
+
+    node.handle(1, ack2(1, 5));
+    node.handle(1, ack2(1, 5));
+    node.handle(1, ack2(1, 4));
+    node.handle(1, ack2(0, 10));
+    assert_eq!(registry.get(&1).unwrap().stats().heartbeat_count, 1);
+
+    node.handle(1, ack2(1, 6));
+    node.handle(1, ack2(2, 0));
+    assert_eq!(registry.get(&1).unwrap().stats().heartbeat_count, 3);
+    assert_eq!(
+        node.peer_state(&1),
+        Some(HeartbeatState {
+            generation: 2,
+            version: 0
+        })
+    );
+}
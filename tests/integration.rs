@@ -137,3 +137,135 @@ fn node_heartbeat_missed_dead_real_clock() {
     thread::sleep(Duration::from_millis(7000));
     assert!(!detector.is_available()); // 8200
 }
+
+#[test]
+fn paused_node_not_suspected() {
+    let intervals = vec![0, 1000, 1000, 10000, 0, 0, 500, 500, 1000];
+    let detector = builder().clock(FakeClock::new(intervals)).build().unwrap();
+
+    detector.heartbeat(); // 0
+    detector.heartbeat(); // 1000
+    detector.heartbeat(); // 2000
+    detector.pause_monitoring();
+    assert_eq!(detector.status(), Status::Paused); // 12000
+    assert_eq!(detector.phi(), 0.0); // 12000
+    detector.resume_monitoring(); // 12000
+    assert_eq!(detector.status(), Status::Available); // 12500
+    detector.heartbeat(); // 13000
+    assert!(detector.is_available()); // 14000
+}
+
+#[test]
+fn resumed_node_dead() {
+    let intervals = vec![0, 1000, 1000, 1000, 5000, 0];
+    let detector = builder().clock(FakeClock::new(intervals)).build().unwrap();
+
+    detector.heartbeat(); // 0
+    detector.heartbeat(); // 1000
+    detector.pause_monitoring();
+    detector.resume_monitoring(); // 2000
+    assert_eq!(detector.status(), Status::Available); // 3000
+    assert_eq!(detector.status(), Status::Unavailable); // 8000
+}
+
+#[test]
+fn node_stats() {
+    let intervals = vec![0, 1000, 1000, 1000, 500];
+    let detector = builder().clock(FakeClock::new(intervals)).build().unwrap();
+
+    let stats = detector.stats(); // 0
+    assert_eq!(stats.sample_count, 2);
+    assert_eq!(stats.mean, Duration::from_secs(1));
+    assert_eq!(stats.last_interval, None);
+    assert_eq!(stats.time_since_last_heartbeat, None);
+    assert_eq!(stats.phi, 0.0);
+
+    detector.heartbeat(); // 1000
+    detector.heartbeat(); // 2000
+
+    let stats = detector.stats(); // 3000
+    assert_eq!(stats.sample_count, 3);
+    assert_eq!(stats.mean, Duration::from_secs(1));
+    assert_eq!(stats.last_interval, Some(Duration::from_secs(1)));
+    assert_eq!(
+        stats.time_since_last_heartbeat,
+        Some(Duration::from_secs(1))
+    );
+    assert!(stats.std_deviation > Duration::ZERO);
+    assert!(stats.clamped_std_deviation >= Duration::from_millis(10));
+    assert!(stats.phi > 0.0 && stats.phi < stats.threshold);
+    assert_eq!(stats.threshold, 8.0);
+}
+
+#[test]
+fn node_history() {
+    let intervals = vec![0, 1000, 1200, 800, 2000];
+    let detector = builder()
+        .max_sample_size(4)
+        .clock(FakeClock::new(intervals))
+        .build()
+        .unwrap();
+
+    detector.heartbeat(); // 0
+    detector.heartbeat(); // 1000
+    detector.heartbeat(); // 2200
+    detector.heartbeat(); // 3000
+
+    let history: Vec<_> = detector.history_iter().map(|d| d.as_millis()).collect();
+    assert_eq!(history, vec![1250, 1000, 1200, 800]);
+
+    let histogram = detector.histogram(&[Duration::from_millis(1000)]);
+    assert_eq!(histogram.buckets()[0].count, 2);
+    assert_eq!(histogram.buckets()[1].count, 2);
+}
+
+/// Detector implementing only the required methods, as downstream crates do.
+#[derive(Default)]
+struct CountingDetector(AtomicU64);
+
+impl Detector for CountingDetector {
+    fn heartbeat(&self) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn phi(&self) -> f64 {
+        0.0
+    }
+
+    fn is_available(&self) -> bool {
+        self.0.load(Ordering::Relaxed) > 0
+    }
+}
+
+#[test]
+fn custom_detector() {
+    let detector = CountingDetector::default();
+    assert_eq!(detector.status(), Status::Unavailable);
+
+    detector.heartbeat();
+    detector.pause_monitoring();
+    detector.exclude_stall(Duration::from_secs(1));
+    detector.resume_monitoring();
+
+    let stats = detector.stats();
+    assert_eq!(stats.status, Status::Available);
+    assert_eq!(stats.phi, 0.0);
+    assert_eq!(detector.history_iter().len(), 0);
+
+    let registry = Registry::new(|_: &u32| CountingDetector::default());
+    registry.heartbeat(&1);
+    assert!(prometheus::render(registry.detectors()).contains(r#"{peer="1"}"#));
+
+    let history = HistoryIter::from(vec![Duration::from_secs(1), Duration::from_secs(2)]);
+    assert_eq!(history.collect::<Vec<_>>(), vec![
+        Duration::from_secs(1),
+        Duration::from_secs(2)
+    ]);
+}
+
+#[test]
+fn node_name() {
+    let detector = builder().name("10.0.0.1:7000").build().unwrap();
+    assert_eq!(detector.name(), Some("10.0.0.1:7000"));
+    assert_eq!(builder().build().unwrap().name(), None);
+}
@@ -0,0 +1,82 @@
+#![cfg(feature = "metrics")]
+
+use {
+    metrics_util::{
+        debugging::{DebugValue, DebuggingRecorder},
+        CompositeKey,
+    },
+    phi_accrual_failure_detector::*,
+    std::time::Duration,
+};
+
+fn find<'a>(
+    snapshot: &'a [(
+        CompositeKey,
+        Option<metrics::Unit>,
+        Option<metrics::SharedString>,
+        DebugValue,
+    )],
+    name: &str,
+) -> &'a DebugValue {
+    snapshot
+        .iter()
+        .find(|(key, ..)| key.key().name() == name)
+        .map(|(key, .., value)| {
+            assert!(key
+                .key()
+                .labels()
+                .any(|label| label.key() == "peer" && label.value() == "node-1"));
+            value
+        })
+        .unwrap()
+}
+
+#[test]
+fn named_detector_metrics() {
+    let recorder = DebuggingRecorder::new();
+    let snapshotter = recorder.snapshotter();
+    let clock = ManualClock::new();
+
+    metrics::with_local_recorder(&recorder, || {
+        let detector = UnsyncDetector::builder()
+            .name("node-1")
+            .acceptable_heartbeat_pause(Duration::ZERO)
+            .clock(clock.clone())
+            .build()
+            .unwrap();
+
+        detector.heartbeat();
+        clock.advance(Duration::from_secs(1));
+        detector.heartbeat();
+        clock.advance(Duration::from_secs(1));
+        detector.heartbeat();
+
+        clock.advance(Duration::from_secs(10));
+        assert!(!detector.is_available());
+        detector.heartbeat();
+        assert!(detector.is_available());
+    });
+
+    let snapshot = snapshotter.snapshot().into_vec();
+
+    assert_eq!(
+        find(&snapshot, "phi_accrual_heartbeats_total"),
+        &DebugValue::Counter(4)
+    );
+    assert_eq!(
+        find(&snapshot, "phi_accrual_suspicions_total"),
+        &DebugValue::Counter(1)
+    );
+    assert_eq!(
+        find(&snapshot, "phi_accrual_recoveries_total"),
+        &DebugValue::Counter(1)
+    );
+    assert!(matches!(
+        find(&snapshot, "phi_accrual_interval_seconds"),
+        DebugValue::Histogram(intervals) if intervals.len() == 2
+    ));
+    assert!(matches!(
+        find(&snapshot, "phi_accrual_interval_mean_seconds"),
+        DebugValue::Gauge(mean) if mean.into_inner() == 1.0
+    ));
+}
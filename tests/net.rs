@@ -0,0 +1,184 @@
+#![cfg(feature = "net")]
+
+use {
+    phi_accrual_failure_detector::{net::*, *},
+    std::{
+        io,
+        net::UdpSocket,
+        sync::Arc,
+        thread,
+        time::{Duration, Instant},
+    },
+};
+
+fn registry() -> Arc<Registry<u64, SyncDetector>> {
+    Arc::new(Registry::new(|_| SyncDetector::default()))
+}
+
+#[test]
+fn loopback_heartbeats() {
+    let registry = registry();
+    let receiver = HeartbeatReceiver::bind("127.0.0.1:0", registry.clone()).unwrap();
+    let mut sender = HeartbeatSender::bind("127.0.0.1:0", 1)
+        .unwrap()
+        .incarnation(5)
+        .target(receiver.local_addr().unwrap());
+    let mut buf = vec![0; MAX_DATAGRAM_LEN];
+
+    for sequence in 0..3 {
+        assert_eq!(sender.send().unwrap(), sequence);
+
+        let (frame, _) = receiver.recv(&mut buf).unwrap();
+        assert_eq!(frame.node_id, 1);
+        assert_eq!(frame.incarnation, 5);
+        assert_eq!(frame.sequence, sequence);
+        assert!(frame.payload.is_empty());
+    }
+
+    sender.send_with_payload(b"load=0.5").unwrap();
+    let (frame, _) = receiver.recv(&mut buf).unwrap();
+    assert_eq!(frame.payload, b"load=0.5");
+
+    assert!(registry.is_monitoring(&1));
+    assert_eq!(registry.get(&1).unwrap().stats().heartbeat_count, 4);
+}
+
+#[test]
+fn stale_heartbeats_ignored() {
+    let registry = registry();
+    let receiver = HeartbeatReceiver::bind("127.0.0.1:0", registry.clone()).unwrap();
+    let target = receiver.local_addr().unwrap();
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let mut buf = vec![0; MAX_DATAGRAM_LEN];
+
+    let frame = |incarnation, sequence| {
+        wire::Frame {
+            node_id: 1,
+            incarnation,
+            sequence,
+            timestamp: 0,
+            payload: &[],
+        }
+        .to_vec()
+        .unwrap()
+    };
+
+    for (incarnation, sequence) in [(1, 1), (1, 1), (1, 0), (1, 2), (2, 0)] {
+        socket
+            .send_to(&frame(incarnation, sequence), target)
+            .unwrap();
+    }
+
+    let mut received = Vec::new();
+    for _ in 0..3 {
+        let (frame, _) = receiver.recv(&mut buf).unwrap();
+        received.push((frame.incarnation, frame.sequence));
+    }
+
+    assert_eq!(received, vec![(1, 1), (1, 2), (2, 0)]);
+    assert_eq!(registry.get(&1).unwrap().stats().heartbeat_count, 3);
+}
+
+#[test]
+fn removed_nodes_forgotten() {
+    let registry = registry();
+    let receiver = HeartbeatReceiver::bind("127.0.0.1:0", registry.clone()).unwrap();
+    let target = receiver.local_addr().unwrap();
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let mut buf = vec![0; MAX_DATAGRAM_LEN];
+
+    let frame = |sequence| {
+        wire::Frame {
+            node_id: 1,
+            incarnation: 1,
+            sequence,
+            timestamp: 0,
+            payload: &[],
+        }
+        .to_vec()
+        .unwrap()
+    };
+
+    socket.send_to(&frame(5), target).unwrap();
+    assert_eq!(receiver.recv(&mut buf).unwrap().0.sequence, 5);
+
+    registry.remove(&1);
+    socket.send_to(&frame(0), target).unwrap();
+    assert_eq!(receiver.recv(&mut buf).unwrap().0.sequence, 0);
+    assert_eq!(registry.get(&1).unwrap().stats().heartbeat_count, 1);
+}
+
+#[test]
+fn send_to_all_targets() {
+    let registry = registry();
+    let receiver = HeartbeatReceiver::bind("127.0.0.1:0", registry.clone()).unwrap();
+    let mut buf = vec![0; MAX_DATAGRAM_LEN];
+
+    // An IPv6 target can't be reached from an IPv4 socket.
+    let unreachable = "[::1]:9".parse().unwrap();
+    let mut sender = HeartbeatSender::bind("127.0.0.1:0", 1)
+        .unwrap()
+        .target(unreachable)
+        .target(receiver.local_addr().unwrap());
+
+    let err = sender.send().unwrap_err();
+    let err = err.get_ref().unwrap().downcast_ref::<SendError>().unwrap();
+    assert_eq!(err.sequence, 0);
+    assert_eq!(err.failures.len(), 1);
+    assert_eq!(err.failures[0].0, unreachable);
+
+    // The heartbeat still reached the other target.
+    let (frame, _) = receiver.recv(&mut buf).unwrap();
+    assert_eq!(frame.sequence, 0);
+    assert_eq!(registry.get(&1).unwrap().stats().heartbeat_count, 1);
+}
+
+#[test]
+fn oversized_payload_rejected() {
+    let mut sender = HeartbeatSender::bind("127.0.0.1:0", 1)
+        .unwrap()
+        .target("127.0.0.1:9".parse().unwrap());
+
+    let payload = vec![0; MAX_DATAGRAM_LEN - wire::HEADER_LEN + 1];
+    let err = sender.send_with_payload(&payload).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+    // The sequence number isn't consumed by rejected heartbeats.
+    let payload = vec![0; MAX_DATAGRAM_LEN - wire::HEADER_LEN];
+    assert_eq!(sender.send_with_payload(&payload).unwrap(), 0);
+}
+
+#[test]
+fn background_heartbeats() {
+    let registry = registry();
+    let receiver = HeartbeatReceiver::bind("127.0.0.1:0", registry.clone()).unwrap();
+    let target = receiver.local_addr().unwrap();
+    let _receiver = receiver.spawn().unwrap();
+
+    let senders: Vec<_> = (1..=2)
+        .map(|node_id| {
+            HeartbeatSender::bind("127.0.0.1:0", node_id)
+                .unwrap()
+                .target(target)
+                .interval(Duration::from_millis(20))
+                .jitter(Duration::from_millis(5))
+                .spawn()
+        })
+        .collect();
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let heartbeats = |node_id| {
+        registry
+            .get(&node_id)
+            .map_or(0, |detector| detector.stats().heartbeat_count)
+    };
+
+    while heartbeats(1) < 5 || heartbeats(2) < 5 {
+        assert!(Instant::now() < deadline);
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    drop(senders);
+    assert!(registry.is_available(&1));
+    assert!(registry.is_available(&2));
+}
@@ -0,0 +1,202 @@
+use {
+    phi_accrual_failure_detector::{
+        qos::{Mistake, Trace},
+        replay::{Arrival, Replay},
+        *,
+    },
+    std::time::Duration,
+};
+
+fn secs(secs: u64) -> Duration {
+    Duration::from_secs(secs)
+}
+
+#[test]
+fn mistakes() {
+    let mut trace = Trace::new();
+    trace.record(secs(10), false);
+    trace.record(secs(12), true);
+    trace.record(secs(30), false);
+    trace.record(secs(31), true);
+    trace.record(secs(50), false);
+    trace.record(secs(54), true);
+
+    let qos = trace.qos(secs(100));
+
+    assert_eq!(qos.mistakes, vec![
+        Mistake {
+            start: secs(10),
+            end: secs(12)
+        },
+        Mistake {
+            start: secs(30),
+            end: secs(31)
+        },
+        Mistake {
+            start: secs(50),
+            end: secs(54)
+        },
+    ]);
+
+    assert_eq!(qos.mistake_recurrence_times, vec![secs(20), secs(20)]);
+    assert_eq!(qos.good_periods, vec![secs(18), secs(19)]);
+    assert_eq!(qos.mean_mistake_recurrence_time(), Some(secs(20)));
+    assert_eq!(
+        qos.mean_mistake_duration(),
+        Some(Duration::from_secs(7) / 3)
+    );
+    assert_eq!(qos.mean_good_period(), Some(Duration::from_millis(18_500)));
+    assert_eq!(qos.up_time, secs(100));
+    assert!((qos.query_accuracy() - 0.93).abs() < 1e-9);
+    assert_eq!(qos.mistake_rate(), 108.);
+    assert!(qos.detection_times.is_empty());
+    assert_eq!(qos.undetected, 0);
+}
+
+#[test]
+fn detection() {
+    let mut trace = Trace::new();
+    trace.crash(secs(10));
+    trace.record(secs(13), false);
+    trace.recover(secs(20));
+    trace.record(secs(21), true);
+    trace.crash(secs(30));
+    trace.recover(secs(40));
+    trace.crash(secs(50));
+    trace.record(secs(52), false);
+    trace.record(secs(53), true);
+    trace.record(secs(55), false);
+
+    let qos = trace.qos(secs(60));
+
+    // The second crash is never detected, and the detector trusts the process
+    // for a while during the third.
+    assert_eq!(qos.detection_times, vec![secs(3), secs(5)]);
+    assert_eq!(qos.undetected, 1);
+    assert_eq!(qos.mean_detection_time(), Some(secs(4)));
+    assert_eq!(qos.max_detection_time(), Some(secs(5)));
+
+    // Suspecting a recovered process is a mistake.
+    assert_eq!(qos.mistakes, vec![Mistake {
+        start: secs(20),
+        end: secs(21)
+    }]);
+
+    assert_eq!(qos.up_time, secs(30));
+    assert!(qos.mistake_recurrence_times.is_empty());
+    assert!(qos.good_periods.is_empty());
+}
+
+#[test]
+fn suspected_before_crash() {
+    let mut trace = Trace::new();
+    trace.record(secs(8), false);
+    trace.crash(secs(10));
+
+    let qos = trace.qos(secs(20));
+
+    assert_eq!(qos.detection_times, vec![Duration::ZERO]);
+    assert_eq!(qos.mistakes, vec![Mistake {
+        start: secs(8),
+        end: secs(10)
+    }]);
+
+    // The mistake ending with the crash is not followed by a good period.
+    assert!(qos.good_periods.is_empty());
+    assert_eq!(qos.query_accuracy(), 0.8);
+}
+
+#[test]
+fn empty_trace() {
+    let qos = Trace::new().qos(Duration::ZERO);
+
+    assert_eq!(qos.up_time, Duration::ZERO);
+    assert_eq!(qos.query_accuracy(), 1.);
+    assert_eq!(qos.mistake_rate(), 0.);
+    assert_eq!(qos.mean_detection_time(), None);
+}
+
+#[test]
+#[should_panic]
+fn out_of_order() {
+    let mut trace = Trace::new();
+    trace.record(secs(2), false);
+    trace.record(secs(1), true);
+}
+
+#[test]
+fn observe_detector() {
+    let clock = ManualClock::new();
+    let detector = UnsyncDetector::builder()
+        .acceptable_heartbeat_pause(Duration::ZERO)
+        .clock(clock.clone())
+        .build()
+        .unwrap();
+
+    let mut trace = Trace::new();
+
+    let step = |ms: u64, trace: &mut Trace| {
+        clock.advance(Duration::from_millis(ms));
+        trace.observe(clock.now(), &detector);
+    };
+
+    // Heartbeats every second, with a single missing one followed by a 4s
+    // delay, then a crash.
+    for i in 0..30 {
+        if i != 10 {
+            detector.heartbeat();
+        }
+
+        for _ in 0..10 {
+            step(100, &mut trace);
+        }
+
+        if i == 10 {
+            for _ in 0..40 {
+                step(100, &mut trace);
+            }
+        }
+    }
+
+    trace.crash(clock.now());
+
+    for _ in 0..100 {
+        step(100, &mut trace);
+    }
+
+    let qos = trace.qos(clock.now());
+
+    assert_eq!(qos.mistakes.len(), 1);
+    assert!(qos.mistakes[0].start > secs(10));
+    assert!(qos.mistakes[0].end <= Duration::from_millis(15_100));
+    assert!(qos.query_accuracy() < 1.);
+    assert_eq!(qos.undetected, 0);
+    assert!(qos.detection_times[0] < secs(5));
+}
+
+#[test]
+fn replay_trace() {
+    let arrivals = [0, 1, 2, 3, 4, 10, 11, 12]
+        .map(|secs| Arrival {
+            peer: "a".to_owned(),
+            time: Duration::from_secs(secs),
+        })
+        .to_vec();
+
+    let report = Replay::new(Config::default())
+        .end(secs(30))
+        .run(arrivals)
+        .unwrap()
+        .remove(0);
+
+    assert_eq!(report.false_positives(), 1);
+
+    let mut trace = report.trace();
+    trace.crash(report.last_heartbeat);
+    let qos = trace.qos(secs(30));
+
+    assert_eq!(qos.mistakes.len(), 1);
+    assert_eq!(qos.mistakes[0].end, secs(10));
+    assert_eq!(qos.mistakes[0].duration(), report.mistake_duration());
+    assert_eq!(qos.detection_times, vec![report.detection_time().unwrap()]);
+}
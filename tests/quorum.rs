@@ -0,0 +1,177 @@
+use {
+    phi_accrual_failure_detector::{quorum::*, transport::*, *},
+    std::{sync::Arc, time::Duration},
+};
+
+type Observer =
+    Confirmer<u64, FailureDetector<SyncState<ManualClock>>, InMemoryTransport<u64, Message<u64>>>;
+
+const SUBJECT: u64 = 9;
+
+struct Cluster {
+    clock: ManualClock,
+    network: InMemoryNetwork<u64, Message<u64>>,
+    observers: Vec<Observer>,
+    // Observers the subject's heartbeats reach.
+    reachable: Vec<bool>,
+    verdicts: Vec<(u64, Verdict<u64>)>,
+}
+
+impl Cluster {
+    fn new(size: u64, configure: impl Fn(Observer) -> Observer) -> Self {
+        let clock = ManualClock::new();
+        let network = InMemoryNetwork::new();
+
+        let observers = (0..size)
+            .map(|id| {
+                let clock = clock.clone();
+                let registry = Arc::new(Registry::new(move |_| {
+                    SyncDetector::builder()
+                        .acceptable_heartbeat_pause(Duration::ZERO)
+                        .clock(clock.clone())
+                        .build()
+                        .unwrap()
+                }));
+
+                configure(
+                    Confirmer::new(id, network.endpoint(id), registry, (0..size).collect())
+                        .seed(id),
+                )
+            })
+            .collect();
+
+        Self {
+            clock,
+            network,
+            observers,
+            reachable: vec![true; size as usize],
+            verdicts: Vec::new(),
+        }
+    }
+
+    fn run(&mut self, ticks: usize) {
+        for _ in 0..ticks {
+            self.clock.advance(Duration::from_secs(1));
+
+            for (observer, reachable) in self.observers.iter_mut().zip(&self.reachable) {
+                if *reachable {
+                    observer.registry().heartbeat(&SUBJECT);
+                }
+
+                let verdicts = observer.tick();
+                let id = *observer.id();
+                self.verdicts
+                    .extend(verdicts.into_iter().map(|verdict| (id, verdict)));
+            }
+
+            let mut delivered = true;
+            while delivered {
+                delivered = false;
+
+                for observer in &mut self.observers {
+                    while let Some((from, message)) = self.network.recv(observer.id()) {
+                        if let Some(verdict) = observer.handle(from, message) {
+                            self.verdicts.push((*observer.id(), verdict));
+                        }
+                        delivered = true;
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn crash_confirmed() {
+    let mut cluster = Cluster::new(5, |observer| observer);
+    cluster.run(10);
+    assert!(cluster.verdicts.is_empty());
+
+    cluster.reachable = vec![false; 5];
+    cluster.run(10);
+
+    for observer in &cluster.observers {
+        assert!(observer.is_confirmed(&SUBJECT));
+    }
+
+    // Every observer reaches the verdict once, possibly after rejections while
+    // the others' detectors were still catching up.
+    let confirmed: Vec<_> = cluster
+        .verdicts
+        .iter()
+        .filter_map(|(id, verdict)| match verdict {
+            Verdict::Confirmed { subject, votes } => {
+                assert_eq!(*subject, SUBJECT);
+                assert!(*votes >= 3);
+                Some(*id)
+            }
+            Verdict::Rejected { .. } => None,
+        })
+        .collect();
+    assert_eq!(confirmed.len(), 5);
+
+    // Verdicts are reset once the subject recovers.
+    cluster.reachable = vec![true; 5];
+    cluster.run(1);
+    assert!(cluster
+        .observers
+        .iter()
+        .all(|observer| !observer.is_confirmed(&SUBJECT)));
+}
+
+#[test]
+fn bad_link_rejected() {
+    let mut cluster = Cluster::new(5, |observer| observer.retry_interval(3));
+    cluster.run(10);
+
+    // Only the link between the subject and observer 0 is broken.
+    cluster.reachable[0] = false;
+    cluster.run(20);
+
+    assert!(!cluster.verdicts.is_empty());
+    for (id, verdict) in &cluster.verdicts {
+        assert_eq!(*id, 0);
+        assert_eq!(*verdict, Verdict::Rejected {
+            subject: SUBJECT,
+            votes: 1
+        });
+    }
+
+    assert!(!cluster.observers[0].is_confirmed(&SUBJECT));
+}
+
+#[test]
+fn unanswered_round_times_out() {
+    let mut cluster = Cluster::new(3, |observer| observer.k(2).timeout(2));
+    cluster.run(10);
+
+    cluster.network.isolate(&0);
+    cluster.reachable = vec![false; 3];
+    cluster.run(10);
+
+    // Observer 0 can't reach the quorum on its own, while the others confirm
+    // the crash together.
+    assert!(!cluster.observers[0].is_confirmed(&SUBJECT));
+    assert!(cluster.observers[1].is_confirmed(&SUBJECT));
+    assert!(cluster.observers[2].is_confirmed(&SUBJECT));
+    assert!(cluster.verdicts.contains(&(0, Verdict::Rejected {
+        subject: SUBJECT,
+        votes: 1
+    })));
+}
+
+#[test]
+fn local_quorum() {
+    let mut cluster = Cluster::new(3, |observer| observer.quorum(1));
+    cluster.run(10);
+
+    cluster.reachable[0] = false;
+    cluster.run(10);
+
+    assert!(cluster.observers[0].is_confirmed(&SUBJECT));
+    assert_eq!(cluster.verdicts, [(0, Verdict::Confirmed {
+        subject: SUBJECT,
+        votes: 1
+    })]);
+    assert_eq!(cluster.network.pending(&1), 0);
+}
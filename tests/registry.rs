@@ -0,0 +1,293 @@
+use {phi_accrual_failure_detector::*, std::time::Duration};
+
+fn registry(
+    clock: &ManualClock,
+) -> Registry<&'static str, FailureDetector<SyncState<ManualClock>>> {
+    let clock = clock.clone();
+
+    Registry::new(move |_| {
+        SyncDetector::builder()
+            .acceptable_heartbeat_pause(Duration::ZERO)
+            .clock(clock.clone())
+            .build()
+            .unwrap()
+    })
+}
+
+#[test]
+fn registry_monitoring() {
+    let clock = ManualClock::new();
+    let registry = registry(&clock);
+
+    assert!(!registry.is_monitoring(&"a"));
+    assert!(registry.is_available(&"a"));
+
+    for _ in 0..3 {
+        registry.heartbeat(&"a");
+        registry.heartbeat(&"b");
+        clock.advance(Duration::from_secs(1));
+    }
+
+    registry.heartbeat(&"b");
+    assert!(registry.is_monitoring(&"a"));
+    assert_eq!(registry.len(), 2);
+
+    clock.advance(Duration::from_secs(5));
+    registry.pause_monitoring(&"b");
+    assert!(!registry.is_available(&"a"));
+    assert!(registry.is_available(&"b"));
+    assert_eq!(registry.get(&"b").unwrap().status(), Status::Paused);
+
+    // Pausing doesn't start monitoring.
+    registry.pause_monitoring(&"c");
+    assert!(!registry.is_monitoring(&"c"));
+
+    registry.remove(&"a");
+    assert!(!registry.is_monitoring(&"a"));
+    assert!(registry.is_available(&"a"));
+
+    registry.reset();
+    assert!(registry.is_empty());
+}
+
+#[test]
+fn local_health_scaling() {
+    let clock = ManualClock::new();
+    let health = LocalHealth::default();
+    let registry = Registry::new({
+        let clock = clock.clone();
+        let health = health.clone();
+        move |_| {
+            SyncDetector::builder()
+                .acceptable_heartbeat_pause(Duration::ZERO)
+                .local_health(health.clone())
+                .clock(clock.clone())
+                .build()
+                .unwrap()
+        }
+    });
+
+    for _ in 0..10 {
+        registry.heartbeat(&"a");
+        registry.heartbeat(&"b");
+        clock.advance(Duration::from_secs(1));
+    }
+
+    clock.advance(Duration::from_millis(650));
+    assert!(!registry.is_available(&"a"));
+    assert!(!registry.is_available(&"b"));
+
+    // A struggling local node makes every detector more tolerant.
+    health.record_failure();
+    assert!(registry.is_available(&"a"));
+    assert!(registry.is_available(&"b"));
+
+    let stats = registry.get(&"a").unwrap().stats();
+    assert_eq!(stats.threshold, 8.0);
+    assert_eq!(stats.effective_threshold, 16.0);
+
+    health.record_success();
+    assert!(!registry.is_available(&"a"));
+}
+
+#[test]
+fn watchdog_stall_excluded() {
+    let clock = ManualClock::new();
+    let registry = registry(&clock);
+    let health = LocalHealth::default();
+    let mut watchdog = Watchdog::new(Duration::from_millis(100))
+        .local_health(health.clone())
+        .clock(clock.clone());
+
+    assert_eq!(watchdog.check(), None);
+
+    for _ in 0..10 {
+        registry.heartbeat(&"a");
+
+        for _ in 0..10 {
+            clock.advance(Duration::from_millis(100));
+            assert_eq!(watchdog.check(), None);
+        }
+    }
+
+    // The whole process stalls, including the watchdog.
+    clock.advance(Duration::from_secs(10));
+    assert!(!registry.is_available(&"a"));
+
+    let stall = watchdog.check().unwrap();
+    assert_eq!(stall, Duration::from_millis(9_900));
+    assert_eq!(health.score(), 1);
+
+    registry.exclude_stall(stall);
+    assert!(registry.is_available(&"a"));
+
+    // The stall is not recorded into the history either.
+    registry.heartbeat(&"a");
+    let stats = registry.get(&"a").unwrap().stats();
+    assert_eq!(stats.last_interval, Some(Duration::from_millis(1_100)));
+    assert!(stats.mean < Duration::from_millis(1_010));
+}
+
+#[test]
+fn watchdog_stall_before_heartbeat() {
+    let clock = ManualClock::new();
+    let registry = registry(&clock);
+    let mut watchdog = Watchdog::new(Duration::from_millis(100)).clock(clock.clone());
+
+    assert_eq!(watchdog.check(), None);
+
+    for _ in 0..10 {
+        registry.heartbeat(&"a");
+        clock.advance(Duration::from_millis(100));
+        assert_eq!(watchdog.check(), None);
+    }
+
+    // A heartbeat is processed after the stall, before the watchdog notices it.
+    clock.advance(Duration::from_secs(10));
+    registry.heartbeat(&"a");
+    clock.advance(Duration::from_millis(50));
+
+    let stall = watchdog.check().unwrap();
+    assert_eq!(stall, Duration::from_millis(9_950));
+
+    // Only the 50ms since that heartbeat are excluded, not the whole stall.
+    registry.exclude_stall(stall);
+    clock.advance(Duration::from_millis(100));
+    registry.heartbeat(&"a");
+
+    let stats = registry.get(&"a").unwrap().stats();
+    assert_eq!(stats.last_interval, Some(Duration::from_millis(100)));
+}
+
+#[test]
+fn reachability_from_registries() {
+    let clock = ManualClock::new();
+    let registries = [registry(&clock), registry(&clock)];
+
+    for _ in 0..3 {
+        for registry in &registries {
+            registry.heartbeat(&"c");
+            registry.heartbeat(&"d");
+        }
+        clock.advance(Duration::from_secs(1));
+    }
+
+    // Only `b` keeps hearing from `d`.
+    registries[1].heartbeat(&"d");
+    clock.advance(Duration::from_secs(4));
+    registries[1].heartbeat(&"d");
+
+    let mut a = Reachability::new();
+    let mut b = Reachability::new();
+    a.update(&"a", &registries[0]);
+    b.update(&"b", &registries[1]);
+    a.merge(&b);
+
+    assert_eq!(a.status(&"c"), ReachabilityStatus::Unreachable);
+    assert!(a.is_unreachable_by_majority(&"c", 2));
+    assert_eq!(a.status(&"d"), ReachabilityStatus::Unreachable);
+    assert!(!a.is_unreachable_by_majority(&"d", 2));
+    assert_eq!(a.observers_unreachable(&"d"), ["a"]);
+}
+
+#[test]
+fn prometheus_render() {
+    let clock = ManualClock::new();
+    let registry = registry(&clock);
+
+    registry.heartbeat(&"a");
+    clock.advance(Duration::from_secs(1));
+    registry.heartbeat(&"a");
+    clock.advance(Duration::from_millis(500));
+
+    let output = prometheus::render(registry.detectors());
+    let lines: Vec<_> = output.lines().collect();
+
+    assert!(lines.contains(&"# TYPE phi_accrual_phi gauge"));
+    assert!(lines.contains(&r#"phi_accrual_available{peer="a"} 1"#));
+    assert!(lines.contains(&r#"phi_accrual_interval_mean_seconds{peer="a"} 1"#));
+    assert!(lines.contains(&"# TYPE phi_accrual_heartbeats_total counter"));
+    assert!(lines.contains(&r#"phi_accrual_heartbeats_total{peer="a"} 2"#));
+    assert!(lines.contains(&r#"phi_accrual_time_since_last_heartbeat_seconds{peer="a"} 0.5"#));
+
+    // Phi of a peer silent for long enough is infinite.
+    clock.advance(Duration::from_secs(3600));
+    assert_eq!(registry.get(&"a").unwrap().phi(), f64::INFINITY);
+
+    let output = prometheus::render(registry.detectors());
+    assert!(output
+        .lines()
+        .any(|line| line == r#"phi_accrual_phi{peer="a"} +Inf"#));
+    assert!(output
+        .lines()
+        .any(|line| line == r#"phi_accrual_available{peer="a"} 0"#));
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn prometheus_http() {
+    use std::{
+        io::{Read, Write},
+        net::TcpStream,
+        sync::Arc,
+    };
+
+    let get = |addr, path| {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(stream, "GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    };
+
+    let clock = ManualClock::new();
+    let registry = Arc::new(registry(&clock));
+    registry.heartbeat(&"a");
+
+    let server = prometheus::MetricsServer::bind("127.0.0.1:0", {
+        let registry = registry.clone();
+        move || prometheus::render(registry.detectors())
+    })
+    .unwrap();
+
+    let response = get(server.local_addr(), "/metrics");
+    assert!(response.starts_with("HTTP/1.1 200 OK"));
+    assert!(response.contains(r#"phi_accrual_heartbeats_total{peer="a"} 1"#));
+
+    registry.heartbeat(&"a");
+    let response = get(server.local_addr(), "/metrics");
+    assert!(response.contains(r#"phi_accrual_heartbeats_total{peer="a"} 2"#));
+
+    let response = get(server.local_addr(), "/");
+    assert!(response.starts_with("HTTP/1.1 404 Not Found"));
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn prometheus_http_idle_client() {
+    use std::{
+        io::{Read, Write},
+        net::TcpStream,
+        time::Instant,
+    };
+
+    let server = prometheus::MetricsServer::bind("127.0.0.1:0", String::new).unwrap();
+
+    // A client connecting without sending a request doesn't block others for
+    // long.
+    let idle = TcpStream::connect(server.local_addr()).unwrap();
+
+    let mut stream = TcpStream::connect(server.local_addr()).unwrap();
+    write!(stream, "GET /metrics HTTP/1.1\r\n\r\n").unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    assert!(response.starts_with("HTTP/1.1 200 OK"));
+
+    // Nor does it block shutdown.
+    let _idle = TcpStream::connect(server.local_addr()).unwrap();
+    let start = Instant::now();
+    drop(server);
+    assert!(start.elapsed() < Duration::from_secs(5));
+
+    drop(idle);
+}
@@ -0,0 +1,120 @@
+use {
+    phi_accrual_failure_detector::{replay::*, *},
+    std::time::Duration,
+};
+
+fn arrivals(peer: &str, times_ms: impl IntoIterator<Item = u64>) -> Vec<Arrival> {
+    times_ms
+        .into_iter()
+        .map(|ms| Arrival {
+            peer: peer.to_owned(),
+            time: Duration::from_millis(ms),
+        })
+        .collect()
+}
+
+fn config() -> Config {
+    Config {
+        acceptable_heartbeat_pause: Duration::ZERO,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn regular_heartbeats() {
+    let reports = Replay::new(config())
+        .sample_interval(Duration::from_millis(500))
+        .run(arrivals("a", (0..=10).map(|i| i * 1000)))
+        .unwrap();
+
+    assert_eq!(reports.len(), 1);
+
+    let report = &reports[0];
+    assert_eq!(report.peer, "a");
+    assert_eq!(report.heartbeats, 11);
+    assert_eq!(report.last_heartbeat, Duration::from_secs(10));
+    assert!(report.suspicions.is_empty());
+    assert_eq!(report.detection_time(), None);
+
+    assert_eq!(report.phi.len(), 21);
+    assert_eq!(report.phi[20].0, Duration::from_secs(10));
+    assert!(report.phi.iter().all(|(_, phi)| *phi < 8.0));
+}
+
+#[test]
+fn detection() {
+    let times = (0..10).map(|i| i * 1000);
+    let mut input = arrivals("a", times.clone());
+    input.extend(arrivals("b", [0, 20_000]));
+
+    let reports = Replay::new(config()).run(input).unwrap();
+    let report = &reports[0];
+
+    assert_eq!(report.suspicions.len(), 1);
+    assert_eq!(report.false_positives(), 0);
+
+    let detection_time = report.detection_time().unwrap();
+    assert!(detection_time > Duration::from_millis(1000));
+    assert!(detection_time < Duration::from_millis(2000));
+
+    // The suspicion starts exactly when a live detector would suspect the peer.
+    let clock = ManualClock::new();
+    let detector = UnsyncDetector::builder()
+        .config(config())
+        .clock(clock.clone())
+        .build()
+        .unwrap();
+
+    for ms in times {
+        clock.set(Duration::from_millis(ms));
+        detector.heartbeat();
+    }
+
+    let start = report.suspicions[0].start.as_millis() as u64;
+    clock.set(Duration::from_millis(start - 1));
+    assert!(detector.is_available());
+    clock.set(Duration::from_millis(start));
+    assert!(!detector.is_available());
+}
+
+#[test]
+fn false_positives() {
+    let times = (0..10)
+        .map(|i| i * 1000)
+        .chain([15_000, 16_000, 30_000, 31_000]);
+    let report = &Replay::new(config())
+        .end(Duration::from_secs(31))
+        .run(arrivals("a", times))
+        .unwrap()[0];
+
+    assert_eq!(report.suspicions.len(), 2);
+    assert_eq!(report.false_positives(), 2);
+    assert_eq!(report.suspicions[0].end, Some(Duration::from_secs(15)));
+    assert_eq!(report.suspicions[1].end, Some(Duration::from_secs(30)));
+    assert_eq!(
+        report.mistake_duration(),
+        Duration::from_secs(45) - report.suspicions[0].start - report.suspicions[1].start
+    );
+    assert_eq!(report.detection_time(), None);
+
+    // A generous pause tolerates the first gap.
+    let report = &Replay::new(Config {
+        acceptable_heartbeat_pause: Duration::from_secs(5),
+        ..config()
+    })
+    .run(arrivals("a", (0..10).map(|i| i * 1000).chain([15_000])))
+    .unwrap()[0];
+
+    assert!(report.suspicions.is_empty());
+}
+
+#[test]
+fn invalid_config() {
+    let result = Replay::new(Config {
+        threshold: 0.,
+        ..config()
+    })
+    .run(arrivals("a", [0]));
+
+    assert!(matches!(result, Err(Error::Threshold)));
+}
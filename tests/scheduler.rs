@@ -0,0 +1,109 @@
+use {phi_accrual_failure_detector::*, std::time::Duration};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn deadlines() {
+    let clock = ManualClock::new();
+    let scheduler = HeartbeatScheduler::new(ms(100)).clock(clock.clone());
+
+    let deadlines: Vec<_> = scheduler.take(4).collect();
+    assert_eq!(deadlines, [ms(0), ms(100), ms(200), ms(300)]);
+
+    let scheduler = HeartbeatScheduler::new(ms(100))
+        .jitter(ms(20))
+        .seed(1)
+        .clock(clock);
+
+    let deadlines: Vec<_> = scheduler.take(100).collect();
+    for pair in deadlines.windows(2) {
+        let interval = pair[1] - pair[0];
+        assert!(interval >= ms(80) && interval <= ms(120));
+    }
+    assert!(deadlines
+        .windows(2)
+        .any(|pair| pair[1] - pair[0] != ms(100)));
+}
+
+#[test]
+fn lateness_tracking() {
+    let clock = ManualClock::new();
+    let mut scheduler = HeartbeatScheduler::new(ms(100)).clock(clock.clone());
+
+    for lateness in [0, 10, 30, 0] {
+        let deadline = scheduler.next().unwrap();
+        clock.advance(scheduler.delay_until(deadline) + ms(lateness));
+        assert_eq!(scheduler.record_sent(), ms(lateness));
+    }
+
+    let stats = scheduler.stats();
+    assert_eq!(stats.sent, 4);
+    assert_eq!(stats.last_lateness, ms(0));
+    assert_eq!(stats.max_lateness, ms(30));
+    assert_eq!(stats.mean_lateness, ms(10));
+
+    // Deadlines missed during a stall are skipped instead of sent in a burst.
+    assert_eq!(scheduler.deadline(), ms(400));
+    clock.advance(ms(1_000));
+    assert_eq!(scheduler.next(), Some(ms(400)));
+    assert_eq!(scheduler.record_sent(), ms(900));
+    assert_eq!(scheduler.next(), Some(ms(1_400)));
+    assert_eq!(scheduler.delay_until(ms(1_400)), ms(100));
+}
+
+#[test]
+fn adaptive_interval() {
+    let clock = ManualClock::new();
+    let mut scheduler = HeartbeatScheduler::new(ms(1_000))
+        .min_interval(ms(200))
+        .max_interval(ms(2_000))
+        .clock(clock);
+
+    // The peer grows suspicious.
+    scheduler.observe_phi(1.5);
+    assert_eq!(scheduler.stats().interval, ms(500));
+    scheduler.observe_phi(3.0);
+    scheduler.observe_phi(3.0);
+    assert_eq!(scheduler.stats().interval, ms(200));
+
+    // Neither suspicious nor idle.
+    scheduler.observe_phi(0.7);
+    assert_eq!(scheduler.stats().interval, ms(200));
+
+    // The interval grows back while the peer is idle.
+    scheduler.observe_phi(0.1);
+    assert_eq!(scheduler.stats().interval, ms(250));
+
+    for _ in 0..10 {
+        scheduler.observe_phi(0.1);
+    }
+    assert_eq!(scheduler.stats().interval, ms(2_000));
+
+    let first = scheduler.next().unwrap();
+    assert_eq!(scheduler.next().unwrap() - first, ms(2_000));
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn tokio_task() {
+    use std::sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    };
+
+    let sent = Arc::new(AtomicU64::new(0));
+    let task = HeartbeatScheduler::new(ms(10)).spawn({
+        let sent = sent.clone();
+        move |scheduler| {
+            sent.fetch_add(1, Ordering::Relaxed);
+            scheduler.observe_phi(0.);
+        }
+    });
+
+    tokio::time::sleep(ms(100)).await;
+    task.abort();
+
+    assert!(sent.load(Ordering::Relaxed) >= 3);
+}
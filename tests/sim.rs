@@ -0,0 +1,198 @@
+#![cfg(feature = "sim")]
+
+use {
+    phi_accrual_failure_detector::{
+        sim::{Event, Jitter, Link, Simulation},
+        *,
+    },
+    std::time::Duration,
+};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+fn secs(secs: u64) -> Duration {
+    Duration::from_secs(secs)
+}
+
+fn simulate(link: Link, seed: u64, until: Duration) -> Simulation {
+    let mut sim = Simulation::new(link).seed(seed);
+    let detector = UnsyncDetector::builder()
+        .clock(sim.clock())
+        .build()
+        .unwrap();
+
+    sim.run_until(&detector, until);
+    sim
+}
+
+#[test]
+fn step_by_step() {
+    let mut sim = Simulation::new(Link::new(ms(250)).latency(ms(10))).sample_interval(ms(100));
+    let detector = UnsyncDetector::builder()
+        .clock(sim.clock())
+        .build()
+        .unwrap();
+
+    assert!(matches!(sim.step(&detector), Event::Sample(sample) if sample.time == ms(0)));
+    assert_eq!(sim.step(&detector), Event::Heartbeat(ms(10)));
+    assert!(matches!(sim.step(&detector), Event::Sample(sample) if sample.time == ms(100)));
+    assert!(matches!(sim.step(&detector), Event::Sample(sample) if sample.time == ms(200)));
+    assert_eq!(sim.step(&detector), Event::Heartbeat(ms(260)));
+    assert_eq!(sim.now(), ms(260));
+    assert_eq!(detector.stats().heartbeat_count, 2);
+    assert_eq!(detector.stats().last_interval, Some(ms(250)));
+}
+
+#[test]
+fn heartbeat_missed_dead() {
+    // Equivalent of `node_heartbeat_missed_dead_real_clock` without sleeping.
+    let link = Link::new(ms(100)).crash(ms(1100));
+    let mut sim = Simulation::new(link);
+    let detector = UnsyncDetector::builder()
+        .acceptable_heartbeat_pause(secs(3))
+        .clock(sim.clock())
+        .build()
+        .unwrap();
+
+    sim.run_until(&detector, ms(1200));
+    assert!(detector.is_available());
+    assert_eq!(sim.arrivals().len(), 11);
+
+    sim.run_until(&detector, ms(8200));
+    assert!(!detector.is_available());
+}
+
+#[test]
+fn deterministic() {
+    let link = Link::new(secs(1))
+        .jitter(Jitter::Normal {
+            std_deviation: ms(200),
+        })
+        .loss(0.1);
+
+    let a = simulate(link.clone(), 42, secs(120));
+    let b = simulate(link.clone(), 42, secs(120));
+    let c = simulate(link, 43, secs(120));
+
+    assert_eq!(a.arrivals(), b.arrivals());
+    assert_eq!(a.samples(), b.samples());
+    assert_eq!(a.trace(), b.trace());
+    assert_ne!(a.arrivals(), c.arrivals());
+}
+
+#[test]
+fn phi_series() {
+    let sim = simulate(Link::new(secs(1)).crash(secs(30)), 0, secs(40));
+
+    assert_eq!(sim.samples().len(), 401);
+    assert!(sim
+        .samples()
+        .iter()
+        .enumerate()
+        .all(|(i, sample)| sample.time == ms(100) * i as u32));
+
+    // Phi grows monotonically after the crash.
+    let after_crash: Vec<_> = sim
+        .samples()
+        .iter()
+        .filter(|sample| sample.time >= secs(30))
+        .collect();
+
+    assert!(after_crash
+        .windows(2)
+        .all(|pair| pair[0].phi <= pair[1].phi));
+    assert!(!after_crash.last().unwrap().available);
+}
+
+#[test]
+fn crash_detection() {
+    let link = Link::new(secs(1))
+        .latency(ms(20))
+        .jitter(Jitter::Normal {
+            std_deviation: ms(50),
+        })
+        .crash(secs(60));
+
+    for seed in 0..10 {
+        let sim = simulate(link.clone(), seed, secs(90));
+        let qos = sim.trace().qos(sim.now());
+
+        assert!(sim.arrivals().iter().all(|arrival| *arrival < secs(61)));
+        assert_eq!(qos.undetected, 0);
+        assert!(qos.mistakes.is_empty());
+
+        // Default acceptable heartbeat pause of 3s.
+        let detection = qos.detection_times[0];
+        assert!(detection > secs(3) && detection < secs(6), "{detection:?}");
+    }
+}
+
+#[test]
+fn loss() {
+    let sim = simulate(Link::new(ms(100)).loss(0.5), 1, secs(1000));
+    let delivered = sim.arrivals().len() as f64 / 10_001.;
+
+    assert!((0.45..0.55).contains(&delivered), "{delivered}");
+}
+
+#[test]
+fn burst_loss() {
+    let sim = simulate(Link::new(ms(100)).burst_loss(0.01, secs(2)), 3, secs(1000));
+    let gaps: Vec<_> = sim
+        .arrivals()
+        .windows(2)
+        .map(|pair| pair[1] - pair[0])
+        .filter(|gap| *gap > ms(100))
+        .collect();
+
+    assert!(!gaps.is_empty());
+    assert!(gaps.iter().all(|gap| *gap >= secs(2)));
+}
+
+#[test]
+fn partition() {
+    let link = Link::new(secs(1)).partition(secs(30), secs(40));
+    let sim = simulate(link, 0, secs(60));
+
+    assert!(!sim
+        .arrivals()
+        .iter()
+        .any(|arrival| (secs(30)..secs(40)).contains(arrival)));
+
+    let qos = sim.trace().qos(sim.now());
+
+    assert_eq!(qos.mistakes.len(), 1);
+    assert_eq!(qos.mistakes[0].end, secs(40));
+    assert!(qos.query_accuracy() < 1.);
+}
+
+#[test]
+fn pareto_jitter() {
+    let pareto = Link::new(secs(1)).jitter(Jitter::Pareto {
+        scale: ms(50),
+        shape: 1.5,
+    });
+
+    let normal = Link::new(secs(1)).jitter(Jitter::Normal {
+        std_deviation: ms(50),
+    });
+
+    let max_gap = |sim: &Simulation| {
+        let mut arrivals = sim.arrivals().to_vec();
+        arrivals.sort();
+        arrivals
+            .windows(2)
+            .map(|pair| pair[1] - pair[0])
+            .max()
+            .unwrap()
+    };
+
+    let pareto = simulate(pareto, 5, secs(3600));
+    let normal = simulate(normal, 5, secs(3600));
+
+    // Heavy tailed delays cause much longer gaps between heartbeats.
+    assert!(max_gap(&pareto) > max_gap(&normal) * 2);
+    assert!(max_gap(&normal) < ms(1500));
+}
@@ -0,0 +1,118 @@
+use {
+    phi_accrual_failure_detector::{stream::*, wire::Frame, Detector, SyncDetector},
+    std::io::{self, Cursor},
+};
+
+fn frame(sequence: u64) -> Frame<'static> {
+    Frame {
+        node_id: 1,
+        incarnation: 2,
+        sequence,
+        timestamp: 3,
+        payload: b"load=0.5",
+    }
+}
+
+#[test]
+fn blocking_roundtrip() {
+    let mut writer = StreamWriter::new(Vec::new());
+    writer.write_data(b"hello").unwrap();
+    writer.write_heartbeat(frame(0)).unwrap();
+    writer.write_data(b"").unwrap();
+    writer.write_heartbeat(frame(1)).unwrap();
+
+    let detector = SyncDetector::default();
+    let reader = StreamReader::new(Cursor::new(writer.into_inner()), &detector);
+    let items = reader.collect::<io::Result<Vec<_>>>().unwrap();
+
+    assert_eq!(items.len(), 4);
+    assert_eq!(items[0], Item::Data(b"hello".to_vec()));
+    assert_eq!(items[2], Item::Data(Vec::new()));
+
+    let Item::Heartbeat(heartbeat) = &items[3] else {
+        panic!("expected heartbeat");
+    };
+    assert_eq!(heartbeat.frame(), frame(1));
+    assert_eq!(detector.stats().heartbeat_count, 2);
+}
+
+#[test]
+fn blocking_invalid_input() {
+    let detector = SyncDetector::default();
+
+    // Truncated message.
+    let mut buf = Vec::new();
+    Message::Data(b"hello").encode(&mut buf).unwrap();
+    buf.pop();
+    let mut reader = StreamReader::new(Cursor::new(buf), &detector);
+    let err = reader.read_item().unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+    // Truncated length prefix.
+    let mut reader = StreamReader::new(Cursor::new(vec![0, 0]), &detector);
+    let err = reader.read_item().unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+    // Unknown message kind.
+    let mut reader = StreamReader::new(Cursor::new(vec![0, 0, 0, 1, 7]), &detector);
+    let err = reader.read_item().unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+    // Malformed heartbeat frame.
+    let mut reader = StreamReader::new(Cursor::new(vec![0, 0, 0, 2, 1, 0]), &detector);
+    let err = reader.read_item().unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+    // Oversized length prefix.
+    let len = (MAX_MESSAGE_LEN as u32 + 1).to_be_bytes();
+    let mut reader = StreamReader::new(Cursor::new(len.to_vec()), &detector);
+    let err = reader.read_item().unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+    // Clean end of stream.
+    let mut reader = StreamReader::new(Cursor::new(Vec::new()), &detector);
+    assert!(reader.read_item().unwrap().is_none());
+
+    assert_eq!(detector.stats().heartbeat_count, 0);
+}
+
+#[cfg(feature = "codec")]
+#[tokio::test]
+async fn codec_duplex() {
+    use {
+        futures_util::{SinkExt, StreamExt},
+        std::sync::Arc,
+        tokio_util::codec::Framed,
+    };
+
+    let detector = Arc::new(SyncDetector::default());
+    let (client, server) = tokio::io::duplex(64);
+    let mut client = Framed::new(client, HeartbeatCodec::new(SyncDetector::default()));
+    let mut server = Framed::new(server, HeartbeatCodec::new(detector.clone()));
+
+    let writer = tokio::spawn(async move {
+        client.send(Message::Data(&[0xab; 100])).await.unwrap();
+        for sequence in 0..3 {
+            client
+                .send(Message::Heartbeat(frame(sequence)))
+                .await
+                .unwrap();
+        }
+    });
+
+    assert_eq!(
+        server.next().await.unwrap().unwrap(),
+        Item::Data(vec![0xab; 100])
+    );
+
+    for sequence in 0..3 {
+        let Item::Heartbeat(heartbeat) = server.next().await.unwrap().unwrap() else {
+            panic!("expected heartbeat");
+        };
+        assert_eq!(heartbeat.frame().sequence, sequence);
+    }
+
+    writer.await.unwrap();
+    assert!(server.next().await.is_none());
+    assert_eq!(detector.stats().heartbeat_count, 3);
+}
@@ -0,0 +1,153 @@
+use {
+    phi_accrual_failure_detector::{
+        replay::Arrival,
+        sweep::{self, Grid, Sweep},
+        Config,
+    },
+    std::time::Duration,
+};
+
+/// Two peers heartbeating every second for 10 minutes, where peer `b` goes
+/// silent for 4s once.
+fn trace() -> Vec<Arrival> {
+    let mut arrivals: Vec<_> = (0..600u64)
+        .flat_map(|i| {
+            let a = Some(("a", i));
+            let b = (!(300..304).contains(&i)).then_some(("b", i));
+            a.into_iter().chain(b)
+        })
+        .map(|(peer, i)| Arrival {
+            peer: peer.to_owned(),
+            time: Duration::from_secs(i),
+        })
+        .collect();
+
+    arrivals.sort_by_key(|arrival| arrival.time);
+    arrivals
+}
+
+fn grid() -> Grid {
+    Grid {
+        thresholds: vec![1., 3., 8., 16.],
+        acceptable_heartbeat_pauses: vec![
+            Duration::ZERO,
+            Duration::from_secs(3),
+            Duration::from_secs(5),
+        ],
+        max_sample_sizes: vec![100],
+    }
+}
+
+#[test]
+fn grid_configs() {
+    let base = Config {
+        min_std_deviation: Duration::from_millis(50),
+        ..Default::default()
+    };
+
+    let configs = grid().configs(&base);
+    assert_eq!(configs.len(), 12);
+    assert!(configs
+        .iter()
+        .all(|config| config.min_std_deviation == Duration::from_millis(50)));
+}
+
+#[test]
+fn sweep_detects_crashes() {
+    let points = Sweep::new(grid())
+        .crash_points(vec![Duration::from_secs(150), Duration::from_secs(450)])
+        .run(&trace())
+        .unwrap();
+
+    assert_eq!(points.len(), 12);
+
+    for point in &points {
+        assert_eq!(point.undetected, 0);
+        assert!(point.mean_detection_time <= point.max_detection_time);
+        assert!(point.mean_detection_time >= point.config.acceptable_heartbeat_pause);
+    }
+
+    // The 4s gap is suspected without any pause margin but not with 5s.
+    let strict = &points[0];
+    assert_eq!(strict.config.acceptable_heartbeat_pause, Duration::ZERO);
+    assert!(strict.false_positives >= 1);
+    assert!(strict.mistake_rate > 0.);
+
+    let lenient = points
+        .iter()
+        .find(|point| point.config.acceptable_heartbeat_pause == Duration::from_secs(5))
+        .unwrap();
+    assert_eq!(lenient.false_positives, 0);
+    assert!(lenient.mean_detection_time > strict.mean_detection_time);
+}
+
+#[test]
+fn crash_during_suspicion() {
+    // Peer `b` is already suspected by strict configurations when it crashes
+    // in the middle of its 4s silence.
+    let arrivals: Vec<_> = trace()
+        .into_iter()
+        .filter(|arrival| arrival.peer == "b")
+        .collect();
+    let grid = Grid {
+        thresholds: vec![1.],
+        acceptable_heartbeat_pauses: vec![Duration::ZERO],
+        max_sample_sizes: vec![100],
+    };
+
+    let points = Sweep::new(grid)
+        .crash_points(vec![Duration::from_secs(302)])
+        .run(&arrivals)
+        .unwrap();
+
+    // Detection is measured from the last heartbeat at 299s rather than being
+    // counted as instant.
+    assert_eq!(points[0].undetected, 0);
+    assert!(points[0].mean_detection_time > Duration::from_millis(500));
+    assert!(points[0].mean_detection_time < Duration::from_secs(3));
+}
+
+#[test]
+fn pareto_frontier_is_not_dominated() {
+    let points = Sweep::new(grid()).run(&trace()).unwrap();
+    let frontier = sweep::pareto_frontier(&points);
+
+    assert!(!frontier.is_empty());
+    assert!(frontier.len() <= points.len());
+
+    for point in &frontier {
+        assert!(!points.iter().any(|other| other.dominates(point)));
+    }
+
+    assert!(frontier
+        .windows(2)
+        .all(|pair| pair[0].mean_detection_time <= pair[1].mean_detection_time));
+}
+
+#[test]
+fn recommendation_meets_target() {
+    let points = Sweep::new(grid()).run(&trace()).unwrap();
+
+    let recommended = sweep::recommend(&points, 0.).unwrap();
+    assert_eq!(recommended.false_positives, 0);
+    assert_eq!(recommended.undetected, 0);
+
+    assert!(points
+        .iter()
+        .filter(|point| point.mistake_rate == 0. && point.undetected == 0)
+        .all(|point| point.mean_detection_time >= recommended.mean_detection_time));
+
+    // Any configuration meets an unbounded target, so the fastest one wins.
+    let fastest = sweep::recommend(&points, f64::INFINITY).unwrap();
+    assert!(fastest.mean_detection_time <= recommended.mean_detection_time);
+}
+
+#[test]
+fn invalid_config() {
+    let grid = Grid {
+        thresholds: vec![0.],
+        ..grid()
+    };
+
+    assert!(Sweep::new(grid).run(&trace()).is_err());
+}
@@ -0,0 +1,200 @@
+use {
+    phi_accrual_failure_detector::{swim::*, transport::*, *},
+    std::time::Duration,
+};
+
+type Node =
+    Swim<u64, FailureDetector<UnsyncState<ManualClock>>, InMemoryTransport<u64, Message<u64>>>;
+
+const TICK_MS: u64 = 100;
+
+struct Cluster {
+    clock: ManualClock,
+    network: InMemoryNetwork<u64, Message<u64>>,
+    nodes: Vec<Node>,
+    health: Vec<LocalHealth>,
+    crashed: Vec<u64>,
+}
+
+impl Cluster {
+    /// Creates a cluster of `size` nodes, all joining through the first one.
+    fn new(size: u64) -> Self {
+        let clock = ManualClock::new();
+        let network = InMemoryNetwork::new();
+
+        let health: Vec<_> = (0..size).map(|_| LocalHealth::default()).collect();
+        let nodes = (0..size)
+            .map(|id| {
+                let clock = clock.clone();
+                let local_health = health[id as usize].clone();
+                let mut node = Swim::new(id, network.endpoint(id), move |_| {
+                    UnsyncDetector::builder()
+                        .first_heartbeat_estimate(Duration::from_millis(500))
+                        .acceptable_heartbeat_pause(Duration::ZERO)
+                        .local_health(local_health.clone())
+                        .clock(clock.clone())
+                        .build()
+                        .unwrap()
+                })
+                .local_health(health[id as usize].clone())
+                .seed(id);
+
+                if id != 0 {
+                    node.join(0);
+                }
+
+                node
+            })
+            .collect();
+
+        Self {
+            clock,
+            network,
+            nodes,
+            health,
+            crashed: Vec::new(),
+        }
+    }
+
+    fn crash(&mut self, id: u64) {
+        self.network.isolate(&id);
+        self.crashed.push(id);
+    }
+
+    fn run(&mut self, ticks: usize) {
+        for _ in 0..ticks {
+            self.clock.advance(Duration::from_millis(TICK_MS));
+
+            for node in &mut self.nodes {
+                if self.crashed.contains(node.id()) {
+                    continue;
+                }
+
+                node.tick();
+
+                while let Some((from, message)) = self.network.recv(node.id()) {
+                    node.handle(from, message);
+                }
+            }
+        }
+    }
+
+    /// Returns the state of `subject` as seen by each live node.
+    fn view(&self, subject: u64) -> Vec<Option<MemberState>> {
+        self.nodes
+            .iter()
+            .filter(|node| *node.id() != subject && !self.crashed.contains(node.id()))
+            .map(|node| node.state(&subject))
+            .collect()
+    }
+}
+
+#[test]
+fn membership_convergence() {
+    let mut cluster = Cluster::new(6);
+    cluster.run(100);
+
+    for node in &cluster.nodes {
+        assert_eq!(node.members().count(), 5);
+        assert!(node
+            .members()
+            .all(|(_, member)| member.state() == MemberState::Alive));
+        assert_eq!(node.incarnation(), 0);
+    }
+}
+
+#[test]
+fn crashed_member_declared_dead() {
+    let mut cluster = Cluster::new(6);
+    cluster.run(100);
+    cluster.crash(3);
+
+    // The detector tolerates a few missed acks before indirect probes start.
+    cluster.run(20);
+    assert!(cluster
+        .view(3)
+        .iter()
+        .all(|state| *state != Some(MemberState::Dead)));
+
+    cluster.run(200);
+    assert!(cluster
+        .view(3)
+        .iter()
+        .all(|state| *state == Some(MemberState::Dead)));
+
+    for id in [0, 1, 2, 4, 5] {
+        assert!(cluster
+            .view(id)
+            .iter()
+            .all(|state| *state == Some(MemberState::Alive)));
+    }
+}
+
+#[test]
+fn indirect_probes_prevent_suspicion() {
+    let mut cluster = Cluster::new(5);
+    cluster.run(100);
+
+    // Only the direct link is broken, other members can still reach node 1.
+    cluster.network.disconnect(&0, &1);
+    cluster.run(300);
+
+    assert!(cluster
+        .view(1)
+        .iter()
+        .all(|state| *state == Some(MemberState::Alive)));
+    assert!(cluster
+        .view(0)
+        .iter()
+        .all(|state| *state == Some(MemberState::Alive)));
+}
+
+#[test]
+fn suspicion_refuted() {
+    let mut cluster = Cluster::new(4);
+    cluster.run(100);
+
+    // Falsely suspect node 1 through node 2.
+    cluster.nodes[0].handle(2, Message::Ping {
+        seq: 0,
+        updates: vec![Update {
+            id: 1,
+            incarnation: 0,
+            state: MemberState::Suspect,
+        }],
+    });
+    assert_eq!(cluster.nodes[0].state(&1), Some(MemberState::Suspect));
+
+    cluster.run(20);
+
+    assert_eq!(cluster.nodes[1].incarnation(), 1);
+    for node in &cluster.nodes[2..] {
+        let member = node.member(&1).unwrap();
+        assert_eq!(member.state(), MemberState::Alive);
+        assert_eq!(member.incarnation(), 1);
+    }
+    assert_eq!(cluster.nodes[0].state(&1), Some(MemberState::Alive));
+}
+
+#[test]
+fn local_health_tracks_missed_acks() {
+    let mut cluster = Cluster::new(5);
+    cluster.run(100);
+    assert!(cluster.health.iter().all(|health| health.score() == 0));
+
+    // Node 0 keeps running, but none of its messages get through.
+    cluster.network.isolate(&0);
+    cluster.run(50);
+
+    let health = &cluster.health[0];
+    assert_eq!(health.score(), health.max_score());
+    assert!(cluster.nodes[0].members().all(|(_, member)| member
+        .detector()
+        .stats()
+        .effective_threshold
+        == 72.0));
+
+    cluster.network.heal();
+    cluster.run(100);
+    assert_eq!(cluster.health[0].score(), 0);
+}
@@ -0,0 +1,72 @@
+#![cfg(feature = "tracing")]
+
+use {
+    phi_accrual_failure_detector::*,
+    std::{
+        io,
+        sync::{Arc, Mutex},
+        time::Duration,
+    },
+    tracing_subscriber::fmt::MakeWriter,
+};
+
+#[derive(Clone, Default)]
+struct Output(Arc<Mutex<Vec<u8>>>);
+
+impl io::Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> MakeWriter<'a> for Output {
+    type Writer = Self;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self.clone()
+    }
+}
+
+#[test]
+fn transition_events() {
+    let output = Output::default();
+    let subscriber = tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::DEBUG)
+        .with_ansi(false)
+        .with_writer(output.clone())
+        .finish();
+    let clock = ManualClock::new();
+
+    tracing::subscriber::with_default(subscriber, || {
+        let detector = UnsyncDetector::builder()
+            .name("node-1")
+            .acceptable_heartbeat_pause(Duration::ZERO)
+            .clock(clock.clone())
+            .build()
+            .unwrap();
+
+        detector.heartbeat();
+        clock.advance(Duration::from_secs(1));
+        detector.heartbeat();
+
+        clock.advance(Duration::from_secs(10));
+        assert!(!detector.is_available());
+        assert!(!detector.is_available());
+        detector.heartbeat();
+        assert!(detector.is_available());
+    });
+
+    let output = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+    let lines: Vec<_> = output.lines().collect();
+
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].contains("WARN") && lines[0].contains("resource suspected"));
+    assert!(lines[0].contains("peer=\"node-1\"") && lines[0].contains("elapsed_ms=10000"));
+    assert!(lines[1].contains("DEBUG") && lines[1].contains("heartbeat interval discarded"));
+    assert!(lines[1].contains("reason=\"unavailable\""));
+    assert!(lines[2].contains("INFO") && lines[2].contains("resource recovered"));
+}